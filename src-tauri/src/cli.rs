@@ -0,0 +1,202 @@
+use std::env;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+#[cfg(target_os = "macos")]
+use tauri::Url;
+use tauri::{AppHandle, Manager};
+use uuid::Uuid;
+
+use crate::file_search::FileIndex;
+use crate::tabs::{self, TabInfo};
+
+/// Argument asking to read a paper from stdin, as in `some-tool | papers -`
+const STDIN_ARG: &str = "-";
+
+/// Open papers passed on the command line (called at startup)
+pub fn open_cli_args(app: &AppHandle) {
+    if env::args().skip(1).any(|arg| arg == STDIN_ARG) {
+        let app = app.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            if let Err(e) = open_stdin_paper(&app) {
+                log::error!("Failed to open paper from stdin: {}", e);
+            }
+        });
+    }
+}
+
+/// Read stdin into a temp file under the cache dir and open it as an ephemeral tab.
+///
+/// The file is deleted when the tab closes and is never added to the file index.
+fn open_stdin_paper(app: &AppHandle) -> Result<String, String> {
+    let mut content = String::new();
+    std::io::stdin()
+        .read_to_string(&mut content)
+        .map_err(|e| format!("Failed to read stdin: {}", e))?;
+
+    let dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| e.to_string())?
+        .join("stdin");
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    let path = dir.join(format!("stdin-{}.md", Uuid::new_v4()));
+    fs::write(&path, content).map_err(|e| e.to_string())?;
+
+    let mut tab = TabInfo::new(
+        "markdown",
+        Some(path.to_string_lossy().to_string()),
+        "stdin".to_string(),
+    );
+    tab.ephemeral = true;
+    tabs::create_tab_with_pool(app, tab)
+}
+
+/// Read the clipboard as text with the platform's own tool
+#[cfg(target_os = "macos")]
+fn read_clipboard() -> Result<String, String> {
+    run_clipboard_command(Command::new("pbpaste"))
+}
+
+/// Read the clipboard as text, trying Wayland then the X11 tools
+#[cfg(target_os = "linux")]
+fn read_clipboard() -> Result<String, String> {
+    let mut wayland = Command::new("wl-paste");
+    wayland.arg("--no-newline");
+    let mut xclip = Command::new("xclip");
+    xclip.args(["-selection", "clipboard", "-o"]);
+    let mut xsel = Command::new("xsel");
+    xsel.args(["--clipboard", "--output"]);
+
+    run_clipboard_command(wayland)
+        .or_else(|_| run_clipboard_command(xclip))
+        .or_else(|_| run_clipboard_command(xsel))
+}
+
+#[cfg(target_os = "windows")]
+fn read_clipboard() -> Result<String, String> {
+    let mut command = Command::new("powershell");
+    command.args(["-NoProfile", "-Command", "Get-Clipboard"]);
+    run_clipboard_command(command)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+fn read_clipboard() -> Result<String, String> {
+    Err("Reading the clipboard isn't supported on this platform".to_string())
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux", target_os = "windows"))]
+fn run_clipboard_command(mut command: Command) -> Result<String, String> {
+    let output = command
+        .output()
+        .map_err(|e| format!("Failed to read clipboard: {}", e))?;
+    if !output.status.success() {
+        return Err("Failed to read clipboard".to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The Markdown file a copied path or `file://` URL points at, with `~` expanded
+fn clipboard_paper_path(text: &str, home: &str) -> Option<PathBuf> {
+    let text = text.trim().trim_matches(['"', '\'']);
+    let path = match text.strip_prefix("file://") {
+        Some(url_path) => urlencoding::decode(url_path).ok()?.into_owned(),
+        None => text.to_string(),
+    };
+    let path = match path.strip_prefix("~/") {
+        Some(rest) if !home.is_empty() => Path::new(home).join(rest),
+        _ => PathBuf::from(path),
+    };
+
+    (path.is_absolute() && tabs::is_supported_file(&path)).then_some(path)
+}
+
+/// Open the Markdown file whose path (or `file://` URL) is on the clipboard in a new tab.
+///
+/// Errors if the clipboard holds anything else, which the UI can ignore.
+#[tauri::command]
+pub fn open_from_clipboard(app: AppHandle) -> Result<String, String> {
+    let text = read_clipboard()?;
+    let home = app.state::<FileIndex>().home_dir().to_string();
+    let path = clipboard_paper_path(&text, &home).ok_or("Clipboard doesn't hold a paper path")?;
+    if !path.is_file() {
+        return Err(format!("Paper not found: {}", path.display()));
+    }
+    tabs::create_tab_with_pool(&app, tabs::tab_for_path(&path.to_string_lossy()))
+}
+
+/// The local Markdown files among URLs the OS asked the app to open
+#[cfg(target_os = "macos")]
+fn opened_paper_paths(urls: &[Url]) -> Vec<PathBuf> {
+    urls.iter()
+        .filter(|url| url.scheme() == "file")
+        .filter_map(|url| url.to_file_path().ok())
+        .filter(|path| tabs::is_supported_file(path))
+        .collect()
+}
+
+/// Open Markdown files handed over by Finder (double-click or "Open With"), each in
+/// the tab already showing it or a new one. macOS delivers these to the running
+/// instance itself, so there's nothing to forward.
+#[cfg(target_os = "macos")]
+pub fn open_documents(app: &AppHandle, urls: &[Url]) {
+    for path in opened_paper_paths(urls) {
+        let path = path.to_string_lossy().to_string();
+        let opened = tabs::switch_to_tab_by_path(app.clone(), path.clone()).and_then(|open| {
+            if open {
+                return Ok(());
+            }
+            tabs::create_tab_with_pool(app, tabs::tab_for_path(&path)).map(|_| ())
+        });
+        if let Err(e) = opened {
+            log::error!("Failed to open {}: {}", path, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clipboard_paths_accept_home_and_file_urls() {
+        let parse = |text: &str| clipboard_paper_path(text, "/home/me");
+        assert_eq!(
+            parse("  ~/papers/a.md\n"),
+            Some(PathBuf::from("/home/me/papers/a.md"))
+        );
+        assert_eq!(
+            parse("file:///p/my%20paper.MD"),
+            Some(PathBuf::from("/p/my paper.MD"))
+        );
+        assert_eq!(parse("'/p/b.md'"), Some(PathBuf::from("/p/b.md")));
+        assert_eq!(parse("papers/a.md"), None);
+        assert_eq!(parse("/p/notes.txt"), None);
+        assert_eq!(parse("https://example.com/a.md"), None);
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn opened_documents_keep_local_markdown_files() {
+        let urls: Vec<Url> = [
+            "file:///p/my%20paper.md",
+            "file:///p/notes.txt",
+            "https://example.com/a.md",
+            "file:///p/B.MD",
+            "file:///p/c.markdown",
+        ]
+        .iter()
+        .map(|u| Url::parse(u).unwrap())
+        .collect();
+        assert_eq!(
+            opened_paper_paths(&urls),
+            vec![
+                PathBuf::from("/p/my paper.md"),
+                PathBuf::from("/p/B.MD"),
+                PathBuf::from("/p/c.markdown")
+            ]
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use serde::Serialize;
+use tauri::menu::{MenuItem, MenuItemBuilder};
+use tauri::{AppHandle, Wry};
+
+use crate::{cli, diagnostics, file_search, layout, reading_list, tabs, window};
+
+/// An action listed in the command palette
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CommandDescriptor {
+    pub id: String,
+    pub title: String,
+    pub accelerator: Option<String>,
+}
+
+/// An app action that both the menu and the command palette dispatch by id
+pub struct AppCommand {
+    pub id: &'static str,
+    pub title: &'static str,
+    pub accelerator: Option<&'static str>,
+    run: fn(&AppHandle) -> Result<(), String>,
+}
+
+impl AppCommand {
+    pub fn run(&self, app: &AppHandle) -> Result<(), String> {
+        (self.run)(app)
+    }
+
+    fn descriptor(&self) -> CommandDescriptor {
+        CommandDescriptor {
+            id: self.id.to_string(),
+            title: self.title.to_string(),
+            accelerator: self.accelerator.map(str::to_string),
+        }
+    }
+}
+
+/// Every invokable action; register new ones here to get them in the palette
+const COMMANDS: &[AppCommand] = &[
+    AppCommand {
+        id: "new_window",
+        title: "New Window",
+        accelerator: Some("CmdOrCtrl+N"),
+        run: |app| window::open_new_window(app.clone()),
+    },
+    AppCommand {
+        id: "new_tab",
+        title: "New Tab",
+        accelerator: Some("CmdOrCtrl+T"),
+        run: tabs::create_tab_internal_from_menu,
+    },
+    AppCommand {
+        id: "close_tab",
+        title: "Close Tab",
+        accelerator: Some("CmdOrCtrl+W"),
+        run: tabs::close_tab_or_window,
+    },
+    AppCommand {
+        id: "next_tab",
+        title: "Next Tab",
+        accelerator: Some("Ctrl+Tab"),
+        run: |app| tabs::next_tab(app.clone()),
+    },
+    AppCommand {
+        id: "prev_tab",
+        title: "Previous Tab",
+        accelerator: Some("Ctrl+Shift+Tab"),
+        run: |app| tabs::prev_tab(app.clone()),
+    },
+    AppCommand {
+        id: "next_paper_tab",
+        title: "Next Paper Tab",
+        accelerator: None,
+        run: |app| tabs::next_paper_tab(app.clone()),
+    },
+    AppCommand {
+        id: "prev_paper_tab",
+        title: "Previous Paper Tab",
+        accelerator: None,
+        run: |app| tabs::prev_paper_tab(app.clone()),
+    },
+    AppCommand {
+        id: "move_tab_left",
+        title: "Move Tab Left",
+        accelerator: Some("CmdOrCtrl+Shift+PageUp"),
+        run: |app| tabs::move_tab_left(app.clone()),
+    },
+    AppCommand {
+        id: "move_tab_right",
+        title: "Move Tab Right",
+        accelerator: Some("CmdOrCtrl+Shift+PageDown"),
+        run: |app| tabs::move_tab_right(app.clone()),
+    },
+    AppCommand {
+        id: "open_next_from_queue",
+        title: "Open Next from Reading List",
+        accelerator: None,
+        run: |app| reading_list::open_next_from_queue(app.clone()).map(|_| ()),
+    },
+    AppCommand {
+        id: "open_from_clipboard",
+        title: "Open Paper from Clipboard",
+        accelerator: None,
+        run: |app| cli::open_from_clipboard(app.clone()).map(|_| ()),
+    },
+    AppCommand {
+        id: window::ALWAYS_ON_TOP_MENU_ID,
+        title: "Toggle Always on Top",
+        accelerator: None,
+        run: |app| window::set_always_on_top(app.clone(), !window::always_on_top(app)),
+    },
+    AppCommand {
+        id: "toggle_tab_bar",
+        title: "Toggle Tab Bar",
+        accelerator: None,
+        run: |app| {
+            layout::set_tab_bar_visible(app.clone(), !layout::get_tab_bar_visible(app.clone()));
+            Ok(())
+        },
+    },
+    AppCommand {
+        id: "refresh_file_index",
+        title: "Refresh File Index",
+        accelerator: None,
+        run: |app| {
+            file_search::refresh_file_index(app.clone());
+            Ok(())
+        },
+    },
+    AppCommand {
+        id: "open_app_data_dir",
+        title: "Open App Data Folder",
+        accelerator: None,
+        run: |app| diagnostics::open_app_data_dir(app.clone()),
+    },
+    AppCommand {
+        id: "clear_cache",
+        title: "Clear Cache",
+        accelerator: None,
+        run: |app| diagnostics::clear_cache(app.clone()),
+    },
+];
+
+/// Look up a registered command by id
+pub fn find(id: &str) -> Option<&'static AppCommand> {
+    COMMANDS.iter().find(|command| command.id == id)
+}
+
+/// Build the menu item for a registered command, with its title and accelerator.
+///
+/// Panics if `id` isn't registered, which is a programming error caught at startup.
+pub fn menu_item(app: &AppHandle, id: &str) -> tauri::Result<MenuItem<Wry>> {
+    let command = find(id).unwrap_or_else(|| panic!("menu item for unregistered command {}", id));
+    let mut builder = MenuItemBuilder::with_id(command.id, command.title);
+    if let Some(accelerator) = command.accelerator {
+        builder = builder.accelerator(accelerator);
+    }
+    builder.build(app)
+}
+
+/// List every action the command palette can run
+#[tauri::command]
+pub fn list_commands() -> Vec<CommandDescriptor> {
+    COMMANDS.iter().map(AppCommand::descriptor).collect()
+}
+
+/// Run a registered action by id, exactly as its menu item would
+#[tauri::command]
+pub fn run_command(app: AppHandle, id: String) -> Result<(), String> {
+    let command = find(&id).ok_or_else(|| format!("Unknown command: {}", id))?;
+    command.run(&app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn command_ids_are_unique_and_listed() {
+        let listed = list_commands();
+        let ids: HashSet<_> = listed.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids.len(), COMMANDS.len());
+
+        let new_tab = find("new_tab").unwrap().descriptor();
+        assert_eq!(new_tab.title, "New Tab");
+        assert_eq!(new_tab.accelerator.as_deref(), Some("CmdOrCtrl+T"));
+        assert!(find("no_such_command").is_none());
+    }
+}
@@ -0,0 +1,234 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, Webview};
+use tauri_plugin_opener::OpenerExt;
+
+use crate::file_search::refresh_file_index;
+use crate::layout;
+use crate::pool::WebviewPool;
+use crate::tabs::TabManager;
+
+/// How many entries the activity log keeps; older ones are dropped
+const ACTIVITY_LOG_CAPACITY: usize = 500;
+
+/// A backend operation recorded by `record`
+#[derive(Debug, Clone, Serialize)]
+pub struct LogEntry {
+    /// When it happened (ms since the Unix epoch)
+    pub timestamp_ms: u64,
+    /// "error", "warn" or "info"
+    pub level: String,
+    /// "tab", "pool" or "index"
+    pub category: String,
+    pub message: String,
+}
+
+/// Recent tab, pool and index operations, kept in release builds too so users can
+/// report what happened (the dev logger only runs under `debug_assertions`)
+pub struct ActivityLog(Mutex<VecDeque<LogEntry>>);
+
+impl ActivityLog {
+    pub fn new() -> Self {
+        Self(Mutex::new(VecDeque::with_capacity(ACTIVITY_LOG_CAPACITY)))
+    }
+
+    fn push(&self, entry: LogEntry) {
+        let mut entries = self.0.lock().unwrap();
+        if entries.len() == ACTIVITY_LOG_CAPACITY {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// Entries oldest first
+    pub fn entries(&self) -> Vec<LogEntry> {
+        self.0.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+/// Log an operation and add it to the activity log (see `get_recent_logs`)
+pub fn record(app: &AppHandle, level: log::Level, category: &str, message: String) {
+    log::log!(level, "{}", message);
+    app.state::<ActivityLog>().push(LogEntry {
+        timestamp_ms: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64),
+        level: level.as_str().to_lowercase(),
+        category: category.to_string(),
+        message,
+    });
+}
+
+/// The last ACTIVITY_LOG_CAPACITY tab, pool and index operations, oldest first
+#[tauri::command]
+pub fn get_recent_logs(app: AppHandle) -> Vec<LogEntry> {
+    app.state::<ActivityLog>().entries()
+}
+
+/// Logical tabs versus the webviews actually alive in the main window
+#[derive(Debug, Serialize)]
+pub struct ResourceSummary {
+    pub tab_count: usize,
+    /// Warm webviews waiting in the pool
+    pub pool_count: usize,
+    /// Tab and pool webviews that exist, including hidden ones
+    pub loaded_webviews: usize,
+}
+
+/// Open the app data directory (sessions and settings) in the file manager.
+///
+/// This is where `tauri_plugin_store` writes `settings::STORE_FILE`, so it doubles
+/// as the way to inspect persisted sessions and settings. The file index isn't
+/// there: `FileIndex` lives in memory and is rebuilt at startup.
+#[tauri::command]
+pub fn open_app_data_dir(app: AppHandle) -> Result<(), String> {
+    let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    app.opener()
+        .open_path(dir.to_string_lossy(), None::<&str>)
+        .map_err(|e| e.to_string())
+}
+
+/// Delete everything under `dir` except the paths in `keep` (and the directories
+/// leading to them)
+fn clear_dir_except(dir: &Path, keep: &[PathBuf]) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if keep.contains(&path) {
+            continue;
+        }
+        if path.is_dir() && !path.is_symlink() {
+            if keep.iter().any(|k| k.starts_with(&path)) {
+                clear_dir_except(&path, keep)?;
+            } else {
+                fs::remove_dir_all(&path)?;
+            }
+        } else {
+            fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Empty the app cache directory and force a fresh file index refresh.
+///
+/// Files backing open ephemeral tabs (papers piped from stdin) live there too and
+/// are kept; they're deleted when their tab closes.
+#[tauri::command]
+pub fn clear_cache(app: AppHandle) -> Result<(), String> {
+    let dir = app.path().app_cache_dir().map_err(|e| e.to_string())?;
+    if dir.exists() {
+        let in_use: Vec<PathBuf> = app
+            .state::<TabManager>()
+            .get_state()
+            .tabs
+            .into_iter()
+            .filter(|t| t.ephemeral)
+            .filter_map(|t| t.paper_path.map(PathBuf::from))
+            .collect();
+        clear_dir_except(&dir, &in_use).map_err(|e| e.to_string())?;
+    }
+    log::info!("Cleared cache directory: {}", dir.display());
+
+    refresh_file_index(app);
+    Ok(())
+}
+
+/// Count tabs, pooled webviews and live content webviews (read-only)
+#[tauri::command]
+pub fn resource_summary(app: AppHandle) -> ResourceSummary {
+    let loaded_webviews = app.get_window("main").map_or(0, |window| {
+        window
+            .webviews()
+            .iter()
+            .filter(|w| layout::is_content_webview(w.label()))
+            .count()
+    });
+
+    ResourceSummary {
+        tab_count: app.state::<TabManager>().tab_count(),
+        pool_count: app.state::<WebviewPool>().size(),
+        loaded_webviews,
+    }
+}
+
+#[cfg(any(debug_assertions, feature = "devtools"))]
+fn open_inspector(webview: &Webview) -> Result<(), String> {
+    webview.open_devtools();
+    Ok(())
+}
+
+#[cfg(not(any(debug_assertions, feature = "devtools")))]
+fn open_inspector(_webview: &Webview) -> Result<(), String> {
+    Err("Devtools are only available in debug builds or with the devtools feature".to_string())
+}
+
+/// Open the web inspector of a tab's webview
+#[tauri::command]
+pub fn open_devtools(app: AppHandle, id: String) -> Result<(), String> {
+    if app.state::<TabManager>().get_tab(&id).is_none() {
+        return Err("Tab not found".to_string());
+    }
+    let webview = app.get_webview(&id).ok_or("Tab webview not found")?;
+    open_inspector(&webview)
+}
+
+/// Open the web inspector of a pooled webview that hasn't been claimed by a tab yet
+#[tauri::command]
+pub fn open_pool_devtools(app: AppHandle, label: String) -> Result<(), String> {
+    if !app.state::<WebviewPool>().contains(&label) {
+        return Err("Pooled webview not found".to_string());
+    }
+    let webview = app.get_webview(&label).ok_or("Pooled webview not found")?;
+    open_inspector(&webview)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn activity_log_drops_the_oldest_entries() {
+        let log = ActivityLog::new();
+        for i in 0..ACTIVITY_LOG_CAPACITY + 2 {
+            log.push(LogEntry {
+                timestamp_ms: i as u64,
+                level: "info".to_string(),
+                category: "tab".to_string(),
+                message: format!("entry {}", i),
+            });
+        }
+
+        let entries = log.entries();
+        assert_eq!(entries.len(), ACTIVITY_LOG_CAPACITY);
+        assert_eq!(entries[0].message, "entry 2");
+        assert_eq!(
+            entries.last().unwrap().timestamp_ms,
+            ACTIVITY_LOG_CAPACITY as u64 + 1
+        );
+    }
+
+    #[test]
+    fn clearing_the_cache_keeps_files_in_use() {
+        let dir = std::env::temp_dir().join(format!("papers-cache-{}", std::process::id()));
+        let stdin = dir.join("stdin");
+        fs::create_dir_all(&stdin).unwrap();
+        fs::create_dir_all(dir.join("webkit")).unwrap();
+        let (open, closed) = (stdin.join("stdin-a.md"), stdin.join("stdin-b.md"));
+        for path in [&open, &closed, &dir.join("webkit/blob"), &dir.join("index")] {
+            fs::write(path, "x").unwrap();
+        }
+
+        clear_dir_except(&dir, std::slice::from_ref(&open)).unwrap();
+        assert!(open.exists());
+        assert!(!closed.exists());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
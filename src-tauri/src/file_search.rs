@@ -1,176 +1,3084 @@
 use nucleo_matcher::pattern::{Atom, AtomKind, CaseMatching, Normalization};
 use nucleo_matcher::{Config, Matcher, Utf32Str};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
 use std::env;
+use std::fs;
+use std::io::{ErrorKind, Read};
+use std::path::{Component, Path, PathBuf};
 use std::process::Command;
-use std::sync::RwLock;
-use std::time::Instant;
-use tauri::{AppHandle, Manager};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::diagnostics;
+use crate::frontmatter;
+use crate::library;
+use crate::settings;
+use crate::tabs::{self, TabManager};
+
+const LAST_QUERY_KEY: &str = "lastSearchQuery";
+const SEARCH_ROOTS_KEY: &str = "searchRoots";
+const RESTRICT_TO_ROOTS_KEY: &str = "restrictToSearchRoots";
+const MATCHER_SETTINGS_KEY: &str = "searchMatcher";
+const COLLECTIONS_KEY: &str = "searchCollections";
+
+/// A named set of roots indexed alongside the search roots (e.g. "blog drafts"),
+/// so it can be searched on its own
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Collection {
+    pub name: String,
+    pub roots: Vec<String>,
+}
+
+/// Search results plus whether the index had anything to search yet
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResponse {
+    pub results: Vec<FileSearchResult>,
+    /// The index holds no papers (typically still indexing), as opposed to no matches
+    pub index_empty: bool,
+}
 
 /// A file search result returned to the frontend
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileSearchResult {
     /// Full absolute path to the file
     pub path: String,
-    /// Path relative to home directory (for display)
+    /// Path relative to its search root or home directory (for display)
     pub display_path: String,
     /// Match score (higher is better)
     pub score: u16,
+    /// Up to two parent folder names (`ml/transformers`), to tell apart papers
+    /// sharing a file name
+    #[serde(default)]
+    pub context: String,
+    /// Whether the paper is a favorite; only `search_files` sets this, listing
+    /// favorites first
+    #[serde(default)]
+    pub is_favorite: bool,
+}
+
+impl FileSearchResult {
+    fn new(path: String, score: u16, roots: &[String], home_dir: &str) -> Self {
+        Self {
+            display_path: format_display_path(&path, roots, home_dir),
+            context: parent_context(&path, home_dir),
+            path,
+            score,
+            is_favorite: false,
+        }
+    }
+
+    fn mark_favorite(mut self, favorites: &HashSet<String>) -> Self {
+        self.is_favorite = favorites.contains(&self.path);
+        self
+    }
+}
+
+/// Nucleo matcher tweaks for search ranking. Path matching is on by default, since
+/// every haystack is a path; the rest are opt-in. Fields missing from a stored
+/// value take their defaults.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MatcherSettings {
+    /// Treat `/` as a path segment boundary (`Config::set_match_paths`), rewarding
+    /// matches at the start of folder and file names over ones after a space.
+    /// Off uses `Config::DEFAULT`'s bonuses.
+    pub match_paths: bool,
+    /// Rank matches closer to the start of the path higher (`Config::prefer_prefix`)
+    pub prefer_prefix: bool,
+    /// Fuzzy match queries ending in `/` too, instead of listing that directory
+    /// (see `directory_query`)
+    pub fuzzy_directory_queries: bool,
+    pub case_mode: CaseMode,
+    /// Extra score for a paper opened just now, shrinking linearly to nothing at
+    /// `recency_window_days` (see `boost_recently_opened`); 0 turns the boost off
+    pub recency_boost: u16,
+    pub recency_window_days: u32,
+}
+
+impl Default for MatcherSettings {
+    fn default() -> Self {
+        Self {
+            match_paths: true,
+            prefer_prefix: false,
+            fuzzy_directory_queries: false,
+            case_mode: CaseMode::default(),
+            recency_boost: 0,
+            recency_window_days: 0,
+        }
+    }
+}
+
+/// How query case affects matching
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaseMode {
+    /// Case-insensitive unless the query contains an uppercase letter
+    #[default]
+    Smart,
+    Insensitive,
+    Sensitive,
+}
+
+impl From<CaseMode> for CaseMatching {
+    fn from(mode: CaseMode) -> Self {
+        match mode {
+            CaseMode::Smart => CaseMatching::Smart,
+            CaseMode::Insensitive => CaseMatching::Ignore,
+            CaseMode::Sensitive => CaseMatching::Respect,
+        }
+    }
+}
+
+impl MatcherSettings {
+    fn config(self) -> Config {
+        let mut config = Config::DEFAULT;
+        if self.match_paths {
+            config.set_match_paths();
+        }
+        config.prefer_prefix = self.prefer_prefix;
+        config
+    }
+}
+
+/// Which papers `search_files` considers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchScope {
+    /// Every indexed paper
+    #[default]
+    All,
+    Favorites,
+    Recents,
+}
+
+/// A folder or paper in the tree returned by `index_tree`
+#[derive(Debug, Clone, Serialize)]
+pub struct TreeNode {
+    /// Folder or file name; a root is named like in display paths (`~` for home)
+    pub name: String,
+    /// Full absolute path
+    pub path: String,
+    /// Display path of a paper, as in search results; None for folders
+    pub display_path: Option<String>,
+    /// Folders first, then papers, each sorted by name
+    pub children: Vec<TreeNode>,
+}
+
+impl TreeNode {
+    fn folder(name: String, path: String) -> Self {
+        Self {
+            name,
+            path,
+            display_path: None,
+            children: Vec::new(),
+        }
+    }
+
+    fn is_file(&self) -> bool {
+        self.display_path.is_some()
+    }
+
+    /// Find or create the sub-folder with this name
+    fn child_folder(&mut self, name: &str) -> &mut TreeNode {
+        let pos = match self
+            .children
+            .iter()
+            .position(|c| !c.is_file() && c.name == name)
+        {
+            Some(pos) => pos,
+            None => {
+                let path = format!("{}/{}", self.path, name);
+                self.children.push(TreeNode::folder(name.to_string(), path));
+                self.children.len() - 1
+            }
+        };
+        &mut self.children[pos]
+    }
+
+    fn sort(&mut self) {
+        self.children.sort_by(|a, b| {
+            a.is_file()
+                .cmp(&b.is_file())
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        for child in &mut self.children {
+            child.sort();
+        }
+    }
+}
+
+/// Aggregate size and age statistics of the indexed papers
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LibraryOverview {
+    pub total_files: usize,
+    pub total_bytes: u64,
+    /// Oldest and newest modification times, in milliseconds since the Unix epoch
+    pub oldest_ms: Option<u64>,
+    pub newest_ms: Option<u64>,
+    /// Papers last modified in each month (`YYYY-MM`, UTC), oldest month first
+    pub by_month: Vec<(String, usize)>,
+}
+
+/// Indexed papers with identical content
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DuplicateGroup {
+    /// SHA-256 of the shared content, hex-encoded
+    pub hash: String,
+    pub size: u64,
+    /// Paths of the copies, sorted
+    pub paths: Vec<String>,
+}
+
+/// Added to the score of a path whose file name starts with the query, so typing
+/// the start of a name puts that file above incidental fuzzy matches
+const FILENAME_PREFIX_BOOST: u16 = 1000;
+
+/// Shorter queries are scored against favorites and recents only: one character
+/// matches nearly every path, so scanning a large index for it isn't worth the wait
+const MIN_FULL_SCAN_QUERY_CHARS: usize = 2;
+
+/// How often `search_files_detailed` may retry refreshing an empty index
+const EMPTY_INDEX_REFRESH_SECS: u64 = 5;
+
+/// Report progress every this many files when computing the overview
+const OVERVIEW_PROGRESS_INTERVAL: usize = 1000;
+
+/// How long the most recent search and index refresh took, for performance tuning
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct PerfStats {
+    /// Scoring and sorting time of the last non-empty search
+    pub last_search_duration_ms: Option<f64>,
+    /// Time to list and store the paths of the last refresh (tags are read afterwards)
+    pub last_refresh_duration_ms: Option<f64>,
+}
+
+fn as_millis_f64(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Search features available on this host
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchCapabilities {
+    /// Backend used to build the file index ("mdfind" or "walk")
+    pub backend: String,
+    /// Whether ripgrep is available for content search
+    pub has_content_search: bool,
+    /// Whether the index is kept live: automatic refreshes on focus run unless
+    /// paused with `pause_file_watching`
+    pub has_watcher: bool,
+    /// File descriptors this process has open, where the OS lists them (`/dev/fd`)
+    pub open_file_descriptors: Option<usize>,
+}
+
+/// A single-path change made by file commands between full refreshes
+#[derive(Debug, Clone)]
+enum IndexChange {
+    Add(String),
+    Remove(String),
+    Rename(String, String),
+}
+
+/// Whether a full refresh is running, and what arrived while it ran
+#[derive(Default)]
+struct RefreshState {
+    running: bool,
+    /// Another refresh was requested meanwhile; it runs when this one finishes
+    rerun: bool,
+    /// Changes made during the refresh, replayed on top of its result
+    pending: Vec<IndexChange>,
+}
+
+/// A refresh claimed with `FileIndex::begin_refresh`.
+///
+/// Dropping it without finishing (a failed or panicking refresh task) releases the
+/// claim, so later refreshes aren't locked out.
+pub struct RefreshClaim<'a> {
+    index: &'a FileIndex,
+    finished: bool,
+}
+
+impl RefreshClaim<'_> {
+    /// Install the refresh's paths (if it succeeded), then replay changes made while
+    /// it ran.
+    ///
+    /// The listing replaces the index; the replayed changes are idempotent, so ones
+    /// it already reflects are no-ops. Returns true if another refresh was requested
+    /// meanwhile, in which case the claim is kept for it.
+    pub fn finish(&mut self, paths: Option<Vec<String>>) -> bool {
+        let mut refresh = self.index.refresh.lock().unwrap();
+        if let Some(paths) = paths {
+            self.index.update(paths);
+        }
+        for change in refresh.pending.drain(..) {
+            self.index.apply(change);
+        }
+        if std::mem::take(&mut refresh.rerun) {
+            return true;
+        }
+        refresh.running = false;
+        self.finished = true;
+        false
+    }
+}
+
+impl Drop for RefreshClaim<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            let mut refresh = self.index.refresh.lock().unwrap();
+            *refresh = RefreshState::default();
+        }
+    }
 }
 
 /// In-memory cache of markdown file paths
 pub struct FileIndex {
-    paths: RwLock<Vec<String>>,
+    /// Swapped wholesale on refresh, so searches hold a snapshot instead of copying
+    paths: RwLock<Arc<Vec<String>>>,
+    /// Front matter tags per path, filled in after each path refresh
+    tags: RwLock<HashMap<String, Vec<String>>>,
+    /// Modification time per path (ms since the Unix epoch), filled in after each path refresh
+    modified: RwLock<HashMap<String, u64>>,
+    /// Size in bytes per path, from the same stat as `modified`
+    sizes: RwLock<HashMap<String, u64>>,
+    /// Modification time each path's tags were read at, so a refresh only re-reads
+    /// the front matter of files that changed (see `refresh_tags`)
+    tags_read_at: RwLock<HashMap<String, u64>>,
+    /// Resolved once, since search formats every result with it (see `resolve_home_dir`)
+    home_dir: String,
+    /// Directories to index; empty means the home directory
+    roots: RwLock<Vec<String>>,
+    /// Named collections, each listed from its own roots and included in `paths`
+    collections: RwLock<Vec<Collection>>,
+    /// Paths of each collection from the last refresh, kept in step with file commands
+    collection_paths: RwLock<HashMap<String, Arc<Vec<String>>>>,
+    /// Whether file commands must stay inside the search roots
+    restrict_to_roots: AtomicBool,
+    matcher: RwLock<MatcherSettings>,
+    /// Set once mdfind turns out to be missing; later refreshes walk the roots
+    use_walk_backend: AtomicBool,
     last_refresh: RwLock<Instant>,
+    perf: RwLock<PerfStats>,
+    /// Overview computed since the last update, with the refresh it was computed for
+    overview: RwLock<Option<(Instant, LibraryOverview)>>,
+    /// Content hashes for `find_duplicate_papers`, with the mtime they were computed at
+    content_hashes: Mutex<HashMap<String, (SystemTime, String)>>,
+    /// Canonical form of each indexed path, with the mtime it was resolved at, so
+    /// deduping a listing doesn't canonicalize every path again (see `dedupe_paths`)
+    canonical: Mutex<HashMap<String, (u64, PathBuf)>>,
+    /// The same for the paths listed in collections
+    collection_canonical: Mutex<HashMap<String, (u64, PathBuf)>>,
+    /// Guards against overlapping full refreshes (see `begin_refresh`)
+    refresh: Mutex<RefreshState>,
+    /// Set by `pause_file_watching`: automatic refreshes are skipped until resumed
+    watching_paused: AtomicBool,
+    /// Set by `freeze_index`: stale-on-focus refreshes are skipped during a reading session
+    frozen: AtomicBool,
 }
 
 impl FileIndex {
     pub fn new() -> Self {
+        Self::with_home_dir(resolve_home_dir())
+    }
+
+    /// An empty index treating `home_dir` as the home directory (tests pass their own)
+    pub fn with_home_dir(home_dir: String) -> Self {
         Self {
-            paths: RwLock::new(Vec::new()),
+            home_dir,
+            paths: RwLock::new(Arc::new(Vec::new())),
+            tags: RwLock::new(HashMap::new()),
+            modified: RwLock::new(HashMap::new()),
+            sizes: RwLock::new(HashMap::new()),
+            tags_read_at: RwLock::new(HashMap::new()),
+            roots: RwLock::new(Vec::new()),
+            collections: RwLock::new(Vec::new()),
+            collection_paths: RwLock::new(HashMap::new()),
+            restrict_to_roots: AtomicBool::new(false),
+            matcher: RwLock::new(MatcherSettings::default()),
+            use_walk_backend: AtomicBool::new(false),
             last_refresh: RwLock::new(Instant::now()),
+            perf: RwLock::new(PerfStats::default()),
+            overview: RwLock::new(None),
+            content_hashes: Mutex::new(HashMap::new()),
+            canonical: Mutex::new(HashMap::new()),
+            collection_canonical: Mutex::new(HashMap::new()),
+            refresh: Mutex::new(RefreshState::default()),
+            watching_paused: AtomicBool::new(false),
+            frozen: AtomicBool::new(false),
         }
     }
 
     pub fn get_paths(&self) -> Vec<String> {
-        self.paths.read().unwrap().clone()
+        self.snapshot().to_vec()
+    }
+
+    /// The current paths, shared rather than copied; later updates don't affect it
+    pub fn snapshot(&self) -> Arc<Vec<String>> {
+        Arc::clone(&self.paths.read().unwrap())
+    }
+
+    /// Replace the indexed paths with `edit`'s copy of them, or keep them if it returns
+    /// None. The copy is made outside the write lock, so searches aren't blocked while a
+    /// large index is cloned; if the paths change meanwhile, `edit` runs again.
+    fn edit_paths(&self, mut edit: impl FnMut(&[String]) -> Option<Vec<String>>) {
+        loop {
+            let current = self.snapshot();
+            let Some(edited) = edit(&current) else {
+                return;
+            };
+            let mut paths = self.paths.write().unwrap();
+            if Arc::ptr_eq(&paths, &current) {
+                *paths = Arc::new(edited);
+                return;
+            }
+        }
     }
 
+    /// Replace the indexed paths, dropping duplicates from overlapping roots
     pub fn update(&self, new_paths: Vec<String>) {
-        *self.paths.write().unwrap() = new_paths;
+        let mut canonical = self.canonical.lock().unwrap();
+        let mut resolved = HashMap::new();
+        let paths = Arc::new(dedupe_paths(new_paths, &mut canonical, &mut resolved));
+        *canonical = resolved;
+        drop(canonical);
+        let old = std::mem::replace(&mut *self.paths.write().unwrap(), paths);
+        // Freeing a large list can take a while; do it after releasing the lock
+        drop(old);
         *self.last_refresh.write().unwrap() = Instant::now();
+        *self.overview.write().unwrap() = None;
     }
 
-    pub fn is_stale(&self, threshold_secs: u64) -> bool {
-        self.last_refresh.read().unwrap().elapsed().as_secs() > threshold_secs
+    /// Claim the refresh, or return None if one is already in flight. That refresh
+    /// then runs again when it finishes, so a request made mid-refresh (e.g. after
+    /// the roots changed) still reaches the index.
+    pub fn begin_refresh(&self) -> Option<RefreshClaim<'_>> {
+        let mut refresh = self.refresh.lock().unwrap();
+        if refresh.running {
+            refresh.rerun = true;
+            return None;
+        }
+        refresh.running = true;
+        Some(RefreshClaim {
+            index: self,
+            finished: false,
+        })
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.paths.read().unwrap().is_empty()
+    /// Apply a change now, and queue it too if a refresh's result would overwrite it
+    fn apply_or_queue(&self, change: IndexChange) {
+        let mut refresh = self.refresh.lock().unwrap();
+        if refresh.running {
+            refresh.pending.push(change.clone());
+        }
+        self.apply(change);
+    }
+
+    fn apply(&self, change: IndexChange) {
+        match change {
+            IndexChange::Add(path) => self.apply_add(&path),
+            IndexChange::Remove(path) => self.apply_remove(&path),
+            IndexChange::Rename(old, new) => self.apply_rename(&old, &new),
+        }
     }
-}
 
-/// Get all markdown files using mdfind (Spotlight)
-fn get_markdown_files_mdfind() -> Result<Vec<String>, String> {
-    let home_dir = env::var("HOME").map_err(|_| "Could not determine home directory")?;
+    /// The cached overview, if the index hasn't changed since it was computed
+    pub fn cached_overview(&self) -> Option<LibraryOverview> {
+        let refreshed = *self.last_refresh.read().unwrap();
+        match &*self.overview.read().unwrap() {
+            Some((at, overview)) if *at == refreshed => Some(overview.clone()),
+            _ => None,
+        }
+    }
 
-    let output = Command::new("mdfind")
-        .args([
-            "kMDItemFSName == '*.md'",
-            "-onlyin",
-            &home_dir,
-        ])
-        .output()
-        .map_err(|e| format!("Failed to run mdfind: {}", e))?;
+    /// Cache an overview computed for the index as of `refreshed`
+    pub fn cache_overview(&self, refreshed: Instant, overview: LibraryOverview) {
+        *self.overview.write().unwrap() = Some((refreshed, overview));
+    }
 
-    if !output.status.success() {
-        return Err(format!(
-            "mdfind failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ));
+    /// Hash a file's content, reusing the cached hash if it hasn't been modified since
+    pub fn content_hash(&self, path: &str, modified: SystemTime) -> Option<String> {
+        if let Some((at, hash)) = self.content_hashes.lock().unwrap().get(path) {
+            if *at == modified {
+                return Some(hash.clone());
+            }
+        }
+
+        let hash = hash_file(Path::new(path)).ok()?;
+        self.content_hashes
+            .lock()
+            .unwrap()
+            .insert(path.to_string(), (modified, hash.clone()));
+        Some(hash)
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    Ok(stdout.lines()
-        .filter(|line| !line.contains("/node_modules/"))
-        .map(|s| s.to_string()).collect())
-}
+    /// Forget cached hashes except those of `paths`. Edits already drop and rekey
+    /// entries; a duplicate scan calls this with the paths it hashed, which clears
+    /// out papers that left the index without a full pass on every update.
+    fn retain_content_hashes<'a>(&self, paths: impl IntoIterator<Item = &'a str>) {
+        let mut hashes = self.content_hashes.lock().unwrap();
+        let kept = paths
+            .into_iter()
+            .filter_map(|path| hashes.remove_entry(path))
+            .collect();
+        *hashes = kept;
+    }
 
-/// Refresh the file index in the background
-#[tauri::command]
-pub fn refresh_file_index(app: AppHandle) {
-    tauri::async_runtime::spawn(async move {
-        match get_markdown_files_mdfind() {
-            Ok(paths) => {
-                let index = app.state::<FileIndex>();
-                index.update(paths);
-                log::info!("File index refreshed");
+    pub fn perf_stats(&self) -> PerfStats {
+        *self.perf.read().unwrap()
+    }
+
+    pub fn record_search_duration(&self, duration: Duration) {
+        self.perf.write().unwrap().last_search_duration_ms = Some(as_millis_f64(duration));
+    }
+
+    pub fn record_refresh_duration(&self, duration: Duration) {
+        self.perf.write().unwrap().last_refresh_duration_ms = Some(as_millis_f64(duration));
+    }
+
+    pub fn update_tags(&self, tags: HashMap<String, Vec<String>>) {
+        *self.tags.write().unwrap() = tags;
+    }
+
+    /// Re-read tags for the files in `stats` (path to size and modified ms) whose
+    /// modification time changed since their tags were last read, keeping the rest
+    pub fn refresh_tags(
+        &self,
+        stats: &HashMap<String, (u64, u64)>,
+        read: impl Fn(&str) -> Vec<String>,
+    ) {
+        let old_tags = self.tags.read().unwrap().clone();
+        let read_at = self.tags_read_at.read().unwrap().clone();
+        let mut tags = HashMap::new();
+        for (path, &(_, modified)) in stats {
+            let paper_tags = if read_at.get(path) == Some(&modified) {
+                old_tags.get(path).cloned().unwrap_or_default()
+            } else {
+                read(path)
+            };
+            if !paper_tags.is_empty() {
+                tags.insert(path.clone(), paper_tags);
+            }
+        }
+        let read_at = stats
+            .iter()
+            .map(|(path, &(_, m))| (path.clone(), m))
+            .collect();
+        *self.tags_read_at.write().unwrap() = read_at;
+        self.update_tags(tags);
+    }
+
+    /// Replace the cached (size, modified ms) of every path
+    pub fn update_file_stats(&self, stats: HashMap<String, (u64, u64)>) {
+        let mut sizes = HashMap::with_capacity(stats.len());
+        let mut modified = HashMap::with_capacity(stats.len());
+        for (path, (size, modified_ms)) in stats {
+            sizes.insert(path.clone(), size);
+            modified.insert(path, modified_ms);
+        }
+        *self.sizes.write().unwrap() = sizes;
+        *self.modified.write().unwrap() = modified;
+    }
+
+    /// A path's cached (size, modified ms), if it was stat-ed since it was indexed
+    fn file_stat(&self, path: &str) -> Option<(u64, u64)> {
+        let size = *self.sizes.read().unwrap().get(path)?;
+        let modified = *self.modified.read().unwrap().get(path)?;
+        Some((size, modified))
+    }
+
+    /// Point indexed paths and their tags at a moved file or folder
+    pub fn rename_path(&self, old: &str, new: &str) {
+        self.apply_or_queue(IndexChange::Rename(old.to_string(), new.to_string()));
+    }
+
+    fn apply_rename(&self, old: &str, new: &str) {
+        let rename = |path: &String| library::moved_path(path, old, new);
+        self.edit_paths(|paths| {
+            paths.iter().any(|p| rename(p).is_some()).then(|| {
+                let moved = |p: &String| rename(p).unwrap_or_else(|| p.clone());
+                paths.iter().map(moved).collect()
+            })
+        });
+
+        // Copies a collection's list only if a search still holds its snapshot
+        for list in self.collection_paths.write().unwrap().values_mut() {
+            for path in Arc::make_mut(list).iter_mut() {
+                if let Some(moved) = rename(path) {
+                    *path = moved;
+                }
+            }
+        }
+
+        rekey_moved(&mut self.tags.write().unwrap(), old, new);
+        rekey_moved(&mut self.modified.write().unwrap(), old, new);
+        rekey_moved(&mut self.sizes.write().unwrap(), old, new);
+        rekey_moved(&mut self.content_hashes.lock().unwrap(), old, new);
+        *self.overview.write().unwrap() = None;
+    }
+
+    /// Add a newly created file so it's searchable before the next refresh
+    pub fn add_path(&self, path: &str) {
+        self.apply_or_queue(IndexChange::Add(path.to_string()));
+    }
+
+    fn apply_add(&self, path: &str) {
+        self.edit_paths(|paths| {
+            (!paths.iter().any(|p| p == path)).then(|| {
+                let mut added = paths.to_vec();
+                added.push(path.to_string());
+                added
+            })
+        });
+
+        let mut collection_paths = self.collection_paths.write().unwrap();
+        for collection in self.collections.read().unwrap().iter() {
+            if !is_within_roots(Path::new(path), &collection.roots) {
+                continue;
             }
-            Err(e) => {
-                log::error!("Failed to refresh file index: {}", e);
+            let list = collection_paths.entry(collection.name.clone()).or_default();
+            if !list.iter().any(|p| p == path) {
+                Arc::make_mut(list).push(path.to_string());
             }
         }
-    });
-}
+        drop(collection_paths);
 
-/// Refresh the file index if it's stale (called on window focus)
-pub fn refresh_if_stale(app: &AppHandle, threshold_secs: u64) {
-    let index = app.state::<FileIndex>();
-    if index.is_stale(threshold_secs) || index.is_empty() {
-        refresh_file_index(app.clone());
+        if let Ok(metadata) = fs::metadata(path) {
+            self.modified
+                .write()
+                .unwrap()
+                .insert(path.to_string(), modified_ms(&metadata));
+            self.sizes
+                .write()
+                .unwrap()
+                .insert(path.to_string(), metadata.len());
+        }
+        *self.overview.write().unwrap() = None;
     }
-}
 
-/// Perform fuzzy search on cached file paths
-#[tauri::command]
-pub fn search_files(app: AppHandle, query: String) -> Vec<FileSearchResult> {
-    let home_dir = env::var("HOME").unwrap_or_default();
-    let index = app.state::<FileIndex>();
-    let files = index.get_paths();
+    /// Drop a deleted file or folder (and everything in it) from the index
+    pub fn remove_path(&self, removed: &str) {
+        self.apply_or_queue(IndexChange::Remove(removed.to_string()));
+    }
 
-    // If query is empty, return first 20 files
-    if query.trim().is_empty() {
-        return files
+    fn apply_remove(&self, removed: &str) {
+        let gone = |path: &String| Path::new(path).starts_with(removed);
+        self.edit_paths(|paths| {
+            paths
+                .iter()
+                .any(gone)
+                .then(|| paths.iter().filter(|p| !gone(p)).cloned().collect())
+        });
+        for list in self.collection_paths.write().unwrap().values_mut() {
+            if list.iter().any(gone) {
+                Arc::make_mut(list).retain(|p| !gone(p));
+            }
+        }
+        self.tags.write().unwrap().retain(|p, _| !gone(p));
+        self.modified.write().unwrap().retain(|p, _| !gone(p));
+        self.sizes.write().unwrap().retain(|p, _| !gone(p));
+        self.content_hashes.lock().unwrap().retain(|p, _| !gone(p));
+        *self.overview.write().unwrap() = None;
+    }
+
+    /// Indexed paths carrying the tag (case-insensitive), in index order
+    pub fn paths_with_tag(&self, tag: &str) -> Vec<String> {
+        let tags = self.tags.read().unwrap();
+        self.snapshot()
+            .iter()
+            .filter(|path| {
+                tags.get(*path)
+                    .is_some_and(|t| t.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Every tag with the number of papers carrying it
+    pub fn tag_counts(&self) -> Vec<(String, usize)> {
+        count_tags(&self.tags.read().unwrap())
+    }
+
+    /// The home directory, or empty if it couldn't be determined
+    pub fn home_dir(&self) -> &str {
+        &self.home_dir
+    }
+
+    /// Configured search roots, falling back to the home directory
+    pub fn get_roots(&self) -> Vec<String> {
+        let roots = self.roots.read().unwrap().clone();
+        if !roots.is_empty() {
+            roots
+        } else if self.home_dir.is_empty() {
+            Vec::new()
+        } else {
+            vec![self.home_dir.clone()]
+        }
+    }
+
+    pub fn set_roots(&self, roots: Vec<String>) {
+        *self.roots.write().unwrap() = roots;
+    }
+
+    /// The search roots followed by every collection's roots
+    pub fn all_roots(&self) -> Vec<String> {
+        let mut roots = self.get_roots();
+        for collection in self.collections.read().unwrap().iter() {
+            roots.extend(collection.roots.iter().cloned());
+        }
+        roots
+    }
+
+    pub fn collections(&self) -> Vec<Collection> {
+        self.collections.read().unwrap().clone()
+    }
+
+    pub fn set_collections(&self, collections: Vec<Collection>) {
+        *self.collections.write().unwrap() = collections;
+    }
+
+    /// Set a collection's roots, adding it if it's new; empty roots remove it
+    pub fn set_collection_roots(&self, name: &str, roots: Vec<String>) {
+        let mut collections = self.collections.write().unwrap();
+        let existing = collections.iter().position(|c| c.name == name);
+        match existing {
+            Some(i) if roots.is_empty() => {
+                collections.remove(i);
+                self.collection_paths.write().unwrap().remove(name);
+            }
+            Some(i) => collections[i].roots = roots,
+            None if roots.is_empty() => {}
+            None => collections.push(Collection {
+                name: name.to_string(),
+                roots,
+            }),
+        }
+    }
+
+    /// Replace the listed paths of every collection, dropping duplicates within each
+    pub fn update_collections(&self, listed: HashMap<String, Vec<String>>) {
+        let mut canonical = self.collection_canonical.lock().unwrap();
+        let mut resolved = HashMap::new();
+        *self.collection_paths.write().unwrap() = listed
             .into_iter()
-            .take(20)
-            .map(|path| {
-                let display_path = path
-                    .strip_prefix(&home_dir)
-                    .map(|p| format!("~{}", p))
-                    .unwrap_or_else(|| path.clone());
-                FileSearchResult {
-                    path,
-                    display_path,
-                    score: 0,
-                }
+            .map(|(name, paths)| {
+                let paths = dedupe_paths(paths, &mut canonical, &mut resolved);
+                (name, Arc::new(paths))
             })
             .collect();
+        *canonical = resolved;
     }
 
-    // Create matcher and pattern
-    let mut matcher = Matcher::new(Config::DEFAULT);
-    let atom = Atom::new(
-        &query,
-        CaseMatching::Smart,
-        Normalization::Smart,
-        AtomKind::Fuzzy,
-        false,
-    );
+    /// A collection's current paths, or None if it hasn't been listed
+    pub fn collection_snapshot(&self, name: &str) -> Option<Arc<Vec<String>>> {
+        self.collection_paths.read().unwrap().get(name).cloned()
+    }
 
-    // Score each file path
-    let mut scored_results: Vec<(String, u16)> = files
-        .into_iter()
-        .filter_map(|path| {
-            // Match against the path without home prefix for better UX
-            let match_target = path.strip_prefix(&home_dir).unwrap_or(&path);
+    /// Keep only the paths inside the named collection's roots (all of them for None)
+    fn within_collection(&self, mut paths: Vec<String>, name: Option<&str>) -> Vec<String> {
+        if let Some(name) = name {
+            let collections = self.collections.read().unwrap();
+            match collections.iter().find(|c| c.name == name) {
+                Some(c) => paths.retain(|path| is_within_roots(Path::new(path), &c.roots)),
+                None => paths.clear(),
+            }
+        }
+        paths
+    }
 
-            // Convert to UTF-32 for nucleo
-            let mut buf = Vec::new();
-            let haystack_str = Utf32Str::new(match_target, &mut buf);
+    pub fn restrict_to_roots(&self) -> bool {
+        self.restrict_to_roots.load(Ordering::Relaxed)
+    }
 
-            // Get score
-            atom.score(haystack_str, &mut matcher)
-                .map(|score| (path, score))
-        })
+    pub fn set_restrict_to_roots(&self, restrict: bool) {
+        self.restrict_to_roots.store(restrict, Ordering::Relaxed);
+    }
+
+    pub fn matcher_settings(&self) -> MatcherSettings {
+        *self.matcher.read().unwrap()
+    }
+
+    pub fn set_matcher_settings(&self, settings: MatcherSettings) {
+        *self.matcher.write().unwrap() = settings;
+    }
+
+    /// Reject paths outside the search roots when the restriction is enabled
+    pub fn check_allowed(&self, path: &Path) -> Result<(), String> {
+        if !self.restrict_to_roots() || is_within_roots(path, &self.all_roots()) {
+            Ok(())
+        } else {
+            Err(format!("{} is outside the search roots", path.display()))
+        }
+    }
+
+    pub fn is_stale(&self, threshold_secs: u64) -> bool {
+        self.last_refresh.read().unwrap().elapsed().as_secs() > threshold_secs
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.read().unwrap().is_empty()
+    }
+
+    pub fn watching_paused(&self) -> bool {
+        self.watching_paused.load(Ordering::Relaxed)
+    }
+
+    /// Pause or resume automatic refreshes, returning whether they were paused
+    fn set_watching_paused(&self, paused: bool) -> bool {
+        self.watching_paused.swap(paused, Ordering::Relaxed)
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen.load(Ordering::Relaxed)
+    }
+
+    /// Freeze or unfreeze the index, returning whether it was frozen
+    fn set_frozen(&self, frozen: bool) -> bool {
+        self.frozen.swap(frozen, Ordering::Relaxed)
+    }
+
+    /// Whether `refresh_if_stale` should refresh: never while watching is paused, and
+    /// while frozen only if the index is still empty
+    fn wants_refresh(&self, threshold_secs: u64) -> bool {
+        if self.watching_paused() {
+            return false;
+        }
+        (self.is_stale(threshold_secs) && !self.is_frozen()) || self.is_empty()
+    }
+}
+
+/// The user's home directory: `HOME`, falling back to `USERPROFILE` (Windows), or
+/// empty if neither is set
+fn resolve_home_dir() -> String {
+    env::var("HOME")
+        .or_else(|_| env::var("USERPROFILE"))
+        .unwrap_or_default()
+}
+
+/// Strip a directory prefix from a path, matching whole components only.
+///
+/// Returns the remainder (empty or starting with a separator), so `/Users/ab` is
+/// not treated as being inside `/Users/a`. A trailing separator on `dir` is ignored.
+fn strip_dir_prefix<'a>(path: &'a str, dir: &str) -> Option<&'a str> {
+    let dir = dir.trim_end_matches(['/', '\\']);
+    if dir.is_empty() {
+        return None;
+    }
+
+    path.strip_prefix(dir)
+        .filter(|rest| rest.is_empty() || rest.starts_with(['/', '\\']))
+}
+
+/// Format a path for display, replacing the home directory prefix with `~`.
+pub fn to_display_path(path: &str, home: &str) -> String {
+    match strip_dir_prefix(path, home) {
+        Some(rest) => format!("~{}", rest),
+        None => path.to_string(),
+    }
+}
+
+/// Format a path for display relative to the most specific (longest) search root.
+///
+/// The home directory is shown as `~`; other roots are shown by their folder name,
+/// e.g. `Documents/notes/a.md` under a `~/Documents` root. Paths outside every
+/// root fall back to `to_display_path`.
+pub fn format_display_path(path: &str, roots: &[String], home: &str) -> String {
+    let root = roots
+        .iter()
+        .filter_map(|root| strip_dir_prefix(path, root).map(|rest| (root, rest)))
+        .max_by_key(|(root, _)| root.trim_end_matches(['/', '\\']).len());
+
+    match root {
+        Some((root, rest)) if strip_dir_prefix(root, home) != Some("") => {
+            let name = Path::new(root.as_str())
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| root.clone());
+            format!("{}{}", name, rest)
+        }
+        _ => to_display_path(path, home),
+    }
+}
+
+/// Move the entries of paths under a moved file or folder to their new keys
+fn rekey_moved<V>(map: &mut HashMap<String, V>, old: &str, new: &str) {
+    let moved: Vec<(String, String)> = map
+        .keys()
+        .filter_map(|path| Some((path.clone(), library::moved_path(path, old, new)?)))
         .collect();
+    for (from, to) in moved {
+        if let Some(value) = map.remove(&from) {
+            map.insert(to, value);
+        }
+    }
+}
 
-    // Sort by score descending
-    scored_results.sort_by(|a, b| b.1.cmp(&a.1));
+/// A file's modification time in milliseconds since the Unix epoch (0 if unavailable)
+fn modified_ms(metadata: &fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_millis() as u64)
+}
 
-    // Take top 20 and convert to result format
-    scored_results
-        .into_iter()
-        .take(20)
-        .map(|(path, score)| {
-            let display_path = path
-                .strip_prefix(&home_dir)
-                .map(|p| format!("~{}", p))
-                .unwrap_or_else(|| path.clone());
-            FileSearchResult {
-                path,
-                display_path,
-                score,
-            }
-        })
-        .collect()
+/// Whether a path lies inside one of the roots (both compared in canonical form).
+pub fn is_within_roots(path: &Path, roots: &[String]) -> bool {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    roots.iter().any(|root| {
+        let root = fs::canonicalize(root).unwrap_or_else(|_| PathBuf::from(root));
+        path.starts_with(root)
+    })
+}
+
+/// Count papers per tag, most common first (ties broken alphabetically).
+///
+/// Tags are lowercased first, since tag search ignores case (see `paths_with_tag`),
+/// so `ML` and `ml` are one tag counted once per paper.
+fn count_tags(tags: &HashMap<String, Vec<String>>) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for paper_tags in tags.values() {
+        let unique: HashSet<String> = paper_tags.iter().map(|t| t.to_ascii_lowercase()).collect();
+        for tag in unique {
+            *counts.entry(tag).or_default() += 1;
+        }
+    }
+
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+/// A path with symlinks and `..` resolved, or as given if it can't be resolved
+pub fn canonical_path(path: &str) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path))
+}
+
+/// The last two folder names above a path, below the home directory (see
+/// `FileSearchResult::context`)
+pub fn parent_context(path: &str, home: &str) -> String {
+    let relative = strip_dir_prefix(path, home).unwrap_or(path);
+    let folders: Vec<_> = Path::new(relative)
+        .parent()
+        .into_iter()
+        .flat_map(Path::components)
+        .filter_map(|c| match c {
+            Component::Normal(name) => Some(name.to_string_lossy()),
+            _ => None,
+        })
+        .collect();
+    folders[folders.len().saturating_sub(2)..].join("/")
+}
+
+/// Remove duplicate paths (compared by canonical form), keeping the first occurrence.
+///
+/// Canonical forms are taken from `cached` while a file's mtime is unchanged, which
+/// costs one stat instead of resolving every component of the path. Each path's
+/// entry moves to `resolved`, so a cache rebuilt from it holds only listed paths.
+fn dedupe_paths(
+    paths: Vec<String>,
+    cached: &mut HashMap<String, (u64, PathBuf)>,
+    resolved: &mut HashMap<String, (u64, PathBuf)>,
+) -> Vec<String> {
+    let mut seen = HashSet::new();
+    paths
+        .into_iter()
+        .filter(|path| {
+            let Ok(metadata) = fs::metadata(path) else {
+                return seen.insert(PathBuf::from(path));
+            };
+            let modified = modified_ms(&metadata);
+            if !resolved.contains_key(path) {
+                let entry = match cached.remove(path) {
+                    Some(entry) if entry.0 == modified => entry,
+                    _ => (modified, canonical_path(path)),
+                };
+                resolved.insert(path.clone(), entry);
+            }
+            seen.insert(resolved[path].1.clone())
+        })
+        .collect()
+}
+
+/// The most recent non-empty search query. The store keeps the last one that opened
+/// a paper, which is what's restored after a restart.
+pub struct LastQuery(Mutex<String>);
+
+impl LastQuery {
+    pub fn new() -> Self {
+        Self(Mutex::new(String::new()))
+    }
+
+    pub fn get(&self) -> String {
+        self.0.lock().unwrap().clone()
+    }
+
+    /// Store the query, returning true if it differs from the previous one
+    pub fn set(&self, query: &str) -> bool {
+        let mut last = self.0.lock().unwrap();
+        if *last == query {
+            return false;
+        }
+        *last = query.to_string();
+        true
+    }
+}
+
+/// Restore the last search query from the store (called at startup)
+pub fn load_last_query(app: &AppHandle) {
+    if let Some(query) = settings::load::<String>(app, LAST_QUERY_KEY) {
+        app.state::<LastQuery>().set(&query);
+    }
+}
+
+/// Persist the query a paper was opened from as the last query.
+///
+/// `search_files` runs on every keystroke, so it only updates the query in memory;
+/// writing the store here instead keeps typing off disk.
+pub fn save_last_query(app: &AppHandle, query: &str) {
+    if query.trim().is_empty() {
+        return;
+    }
+    app.state::<LastQuery>().set(query);
+    if let Err(e) = settings::save(app, LAST_QUERY_KEY, &query) {
+        log::warn!("Failed to persist last search query: {}", e);
+    }
+}
+
+/// Get the most recent non-empty search query
+#[tauri::command]
+pub fn get_last_query(app: AppHandle) -> String {
+    app.state::<LastQuery>().get()
+}
+
+/// Forget the most recent search query
+#[tauri::command]
+pub fn clear_last_query(app: AppHandle) -> Result<(), String> {
+    app.state::<LastQuery>().set("");
+    settings::remove(&app, LAST_QUERY_KEY)
+}
+
+/// Restore search roots and the root restriction from the store (called at startup)
+pub fn load_search_settings(app: &AppHandle) {
+    let index = app.state::<FileIndex>();
+    if let Some(roots) = settings::load::<Vec<String>>(app, SEARCH_ROOTS_KEY) {
+        index.set_roots(roots);
+    }
+    if let Some(restrict) = settings::load::<bool>(app, RESTRICT_TO_ROOTS_KEY) {
+        index.set_restrict_to_roots(restrict);
+    }
+    if let Some(matcher) = settings::load::<MatcherSettings>(app, MATCHER_SETTINGS_KEY) {
+        index.set_matcher_settings(matcher);
+    }
+    if let Some(collections) = settings::load::<Vec<Collection>>(app, COLLECTIONS_KEY) {
+        index.set_collections(collections);
+    }
+}
+
+/// Get the directories being indexed
+#[tauri::command]
+pub fn get_search_roots(app: AppHandle) -> Vec<String> {
+    app.state::<FileIndex>().get_roots()
+}
+
+/// Set the directories to index (empty resets to the home directory) and reindex
+#[tauri::command]
+pub fn set_search_roots(app: AppHandle, roots: Vec<String>) -> Result<(), String> {
+    if let Some(missing) = roots.iter().find(|root| !Path::new(root).is_dir()) {
+        return Err(format!("Not a directory: {}", missing));
+    }
+
+    settings::save(&app, SEARCH_ROOTS_KEY, &roots)?;
+    app.state::<FileIndex>().set_roots(roots);
+    refresh_file_index(app);
+    Ok(())
+}
+
+/// Named collections and their roots, in the order they were added
+#[tauri::command]
+pub fn list_collections(app: AppHandle) -> Vec<Collection> {
+    app.state::<FileIndex>().collections()
+}
+
+/// Set the directories a named collection indexes, adding the collection if it's
+/// new, and reindex. Empty `roots` removes the collection.
+#[tauri::command]
+pub fn set_collection_roots(
+    app: AppHandle,
+    name: String,
+    roots: Vec<String>,
+) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Collection name cannot be empty".to_string());
+    }
+    if let Some(missing) = roots.iter().find(|root| !Path::new(root).is_dir()) {
+        return Err(format!("Not a directory: {}", missing));
+    }
+
+    let index = app.state::<FileIndex>();
+    index.set_collection_roots(name, roots);
+    settings::save(&app, COLLECTIONS_KEY, &index.collections())?;
+    refresh_file_index(app.clone());
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_restrict_to_roots(app: AppHandle) -> bool {
+    app.state::<FileIndex>().restrict_to_roots()
+}
+
+/// Only allow file commands (such as folder listing) inside the search roots
+#[tauri::command]
+pub fn set_restrict_to_roots(app: AppHandle, restrict: bool) -> Result<(), String> {
+    app.state::<FileIndex>().set_restrict_to_roots(restrict);
+    settings::save(&app, RESTRICT_TO_ROOTS_KEY, &restrict)
+}
+
+#[tauri::command]
+pub fn get_matcher_settings(app: AppHandle) -> MatcherSettings {
+    app.state::<FileIndex>().matcher_settings()
+}
+
+/// Change the fuzzy matcher toggles used to rank search results
+#[tauri::command]
+pub fn set_matcher_settings(app: AppHandle, matcher: MatcherSettings) -> Result<(), String> {
+    app.state::<FileIndex>().set_matcher_settings(matcher);
+    settings::save(&app, MATCHER_SETTINGS_KEY, &matcher)
+}
+
+#[tauri::command]
+pub fn get_case_mode(app: AppHandle) -> CaseMode {
+    app.state::<FileIndex>().matcher_settings().case_mode
+}
+
+/// Choose whether an uppercase letter in the query makes search case-sensitive
+#[tauri::command]
+pub fn set_case_mode(app: AppHandle, mode: CaseMode) -> Result<(), String> {
+    let matcher = MatcherSettings {
+        case_mode: mode,
+        ..app.state::<FileIndex>().matcher_settings()
+    };
+    set_matcher_settings(app, matcher)
+}
+
+/// Get all markdown files under the given roots using mdfind (Spotlight).
+///
+/// Returns `Ok(None)` if mdfind isn't installed, as opposed to failing to run.
+fn get_markdown_files_mdfind(roots: &[String]) -> Result<Option<Vec<String>>, String> {
+    if roots.is_empty() {
+        return Err("Could not determine home directory".to_string());
+    }
+
+    let query = tabs::PAPER_EXTENSIONS
+        .iter()
+        .map(|ext| format!("kMDItemFSName == '*.{}'c", ext))
+        .collect::<Vec<_>>()
+        .join(" || ");
+    let mut args = vec![query.as_str()];
+    for root in roots {
+        args.push("-onlyin");
+        args.push(root);
+    }
+
+    let output = match Command::new("mdfind").args(&args).output() {
+        Ok(output) => output,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to run mdfind: {}", e)),
+    };
+
+    if !output.status.success() {
+        return Err(format!(
+            "mdfind failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(Some(
+        stdout
+            .lines()
+            .filter(|line| !line.contains("/node_modules/") && tabs::is_supported_file(line))
+            .map(|s| s.to_string())
+            .collect(),
+    ))
+}
+
+/// Get all markdown files under the given roots by walking the file system
+fn get_markdown_files_walk(roots: &[String]) -> Vec<String> {
+    let mut paths = Vec::new();
+    for root in roots {
+        collect_markdown_files(Path::new(root), true, &mut paths);
+    }
+    paths
+}
+
+/// List markdown files with mdfind, switching to the walk backend for good if it's missing.
+///
+/// The first switch emits `file-index-backend-fallback` so the UI can note that
+/// search may be slower.
+fn get_markdown_files(
+    app: &AppHandle,
+    index: &FileIndex,
+    roots: &[String],
+) -> Result<Vec<String>, String> {
+    if !index.use_walk_backend.load(Ordering::Relaxed) {
+        if let Some(paths) = get_markdown_files_mdfind(roots)? {
+            return Ok(paths);
+        }
+        if !index.use_walk_backend.swap(true, Ordering::Relaxed) {
+            let message = "mdfind not found, falling back to walking search roots".to_string();
+            diagnostics::record(app, log::Level::Warn, "index", message);
+            let _ = app.emit("file-index-backend-fallback", "walk");
+        }
+    }
+    Ok(get_markdown_files_walk(roots))
+}
+
+/// Files found by a refresh
+struct IndexListing {
+    /// Everything listed, possibly with duplicates (dropped by `FileIndex::update`)
+    paths: Vec<String>,
+    /// The paths of each collection
+    collections: HashMap<String, Vec<String>>,
+}
+
+/// List the search roots and then each collection's roots separately
+fn list_index(app: &AppHandle, index: &FileIndex) -> Result<IndexListing, String> {
+    // Temp files (e.g. papers piped from stdin) live in the cache dir
+    let cache_dir = app.path().app_cache_dir().ok();
+    let list = |roots: &[String]| -> Result<Vec<String>, String> {
+        let mut paths = get_markdown_files(app, index, roots)?;
+        if let Some(cache_dir) = &cache_dir {
+            paths.retain(|path| !Path::new(path).starts_with(cache_dir));
+        }
+        Ok(paths)
+    };
+
+    let mut paths = list(&index.get_roots())?;
+    let mut collections = HashMap::new();
+    for collection in index.collections() {
+        let listed = list(&collection.roots)?;
+        paths.extend(listed.iter().cloned());
+        collections.insert(collection.name, listed);
+    }
+    Ok(IndexListing { paths, collections })
+}
+
+/// Collect markdown files in a directory, skipping hidden entries and node_modules
+fn collect_markdown_files(dir: &Path, recursive: bool, out: &mut Vec<String>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') || name == "node_modules" {
+            continue;
+        }
+
+        let path = entry.path();
+        if path.is_dir() {
+            if recursive {
+                collect_markdown_files(&path, recursive, out);
+            }
+        } else if tabs::is_supported_file(&path) {
+            out.push(path.to_string_lossy().to_string());
+        }
+    }
+}
+
+/// List markdown files in a folder (optionally recursively), independent of the index
+#[tauri::command]
+pub fn list_papers_in_dir(
+    app: AppHandle,
+    dir: String,
+    recursive: Option<bool>,
+) -> Result<Vec<FileSearchResult>, String> {
+    let dir_path = Path::new(&dir);
+    if !dir_path.is_dir() {
+        return Err(format!("Not a directory: {}", dir));
+    }
+
+    let index = app.state::<FileIndex>();
+    index.check_allowed(dir_path)?;
+
+    let mut paths = Vec::new();
+    collect_markdown_files(dir_path, recursive.unwrap_or(false), &mut paths);
+
+    let home_dir = index.home_dir();
+    let roots = index.get_roots();
+    let mut results: Vec<FileSearchResult> = paths
+        .into_iter()
+        .map(|path| FileSearchResult::new(path, 0, &roots, home_dir))
+        .collect();
+    results.sort_by(|a, b| a.display_path.cmp(&b.display_path));
+
+    Ok(results)
+}
+
+/// Group the paths under `root` into a folder tree; paths outside it are skipped
+fn build_tree(root: &str, paths: &[String], roots: &[String], home: &str) -> TreeNode {
+    let mut tree = TreeNode::folder(
+        format_display_path(root, &[root.to_string()], home),
+        root.trim_end_matches(['/', '\\']).to_string(),
+    );
+
+    for path in paths {
+        let Some(rest) = strip_dir_prefix(path, root) else {
+            continue;
+        };
+        let parts: Vec<&str> = rest.split(['/', '\\']).filter(|p| !p.is_empty()).collect();
+        let Some((file, dirs)) = parts.split_last() else {
+            continue;
+        };
+
+        let mut node = &mut tree;
+        for dir in dirs {
+            node = node.child_folder(dir);
+        }
+        node.children.push(TreeNode {
+            name: file.to_string(),
+            path: path.clone(),
+            display_path: Some(format_display_path(path, roots, home)),
+            children: Vec::new(),
+        });
+    }
+
+    tree.sort();
+    tree
+}
+
+/// Present the index as a folder tree under `root`.
+///
+/// Without a root, returns an unnamed node with one child per search root.
+#[tauri::command]
+pub fn index_tree(app: AppHandle, root: Option<String>) -> TreeNode {
+    let index = app.state::<FileIndex>();
+    let home_dir = index.home_dir();
+    let paths = index.get_paths();
+    let roots = index.get_roots();
+
+    match root {
+        Some(root) => build_tree(&root, &paths, &roots, home_dir),
+        None => TreeNode {
+            children: roots
+                .iter()
+                .map(|root| build_tree(root, &paths, &roots, home_dir))
+                .collect(),
+            ..TreeNode::folder(String::new(), String::new())
+        },
+    }
+}
+
+/// Format a Unix timestamp in milliseconds as its UTC month, `YYYY-MM`
+fn month_of(ms: u64) -> String {
+    // Civil-from-days conversion (proleptic Gregorian calendar)
+    let days = (ms / 86_400_000) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}", year, month)
+}
+
+/// Summarize (size in bytes, modified ms) pairs into an overview
+fn summarize(entries: impl IntoIterator<Item = (u64, u64)>) -> LibraryOverview {
+    let mut overview = LibraryOverview::default();
+    let mut months: HashMap<String, usize> = HashMap::new();
+
+    for (size, modified_ms) in entries {
+        overview.total_files += 1;
+        overview.total_bytes += size;
+        let oldest = overview.oldest_ms.unwrap_or(modified_ms);
+        overview.oldest_ms = Some(oldest.min(modified_ms));
+        overview.newest_ms = overview.newest_ms.max(Some(modified_ms));
+        *months.entry(month_of(modified_ms)).or_default() += 1;
+    }
+
+    overview.by_month = months.into_iter().collect();
+    overview.by_month.sort();
+    overview
+}
+
+/// Summarize the indexed papers from the sizes and times cached at the last refresh,
+/// only stat-ing paths without them. Emits `library-overview-progress` for large
+/// libraries.
+fn compute_overview(app: &AppHandle, index: &FileIndex) -> LibraryOverview {
+    let paths = index.snapshot();
+    let total = paths.len();
+    let entries = paths.iter().enumerate().filter_map(|(i, path)| {
+        if total > OVERVIEW_PROGRESS_INTERVAL && i % OVERVIEW_PROGRESS_INTERVAL == 0 {
+            let _ = app.emit("library-overview-progress", (i, total));
+        }
+        index.file_stat(path).or_else(|| {
+            let metadata = fs::metadata(path).ok()?;
+            Some((metadata.len(), modified_ms(&metadata)))
+        })
+    });
+    summarize(entries.collect::<Vec<_>>())
+}
+
+/// Disk usage and modification-age histogram of the indexed papers.
+///
+/// Computed on a blocking task and cached until the next index update.
+#[tauri::command]
+pub async fn library_overview(app: AppHandle) -> Result<LibraryOverview, String> {
+    if let Some(overview) = app.state::<FileIndex>().cached_overview() {
+        return Ok(overview);
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let index = app.state::<FileIndex>();
+        let refreshed = *index.last_refresh.read().unwrap();
+        let overview = compute_overview(&app, &index);
+        index.cache_overview(refreshed, overview.clone());
+        overview
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// SHA-256 of a file, streamed so large files aren't read into memory
+fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Group (path, size, hash) entries sharing a hash, keeping groups with several copies
+fn group_duplicates(
+    entries: impl IntoIterator<Item = (String, u64, String)>,
+) -> Vec<DuplicateGroup> {
+    let mut groups: HashMap<String, DuplicateGroup> = HashMap::new();
+    for (path, size, hash) in entries {
+        groups
+            .entry(hash.clone())
+            .or_insert_with(|| DuplicateGroup {
+                hash,
+                size,
+                paths: Vec::new(),
+            })
+            .paths
+            .push(path);
+    }
+
+    let mut duplicates: Vec<DuplicateGroup> = groups
+        .into_values()
+        .filter(|g| g.paths.len() > 1)
+        .map(|mut g| {
+            g.paths.sort();
+            g
+        })
+        .collect();
+    duplicates.sort_by(|a, b| a.paths.cmp(&b.paths));
+    duplicates
+}
+
+/// Hash the indexed papers that share a size with another paper and group the copies
+fn compute_duplicates(index: &FileIndex) -> Vec<DuplicateGroup> {
+    let stats: Vec<(String, u64, SystemTime)> = index
+        .get_paths()
+        .into_iter()
+        .filter_map(|path| {
+            let metadata = fs::metadata(&path).ok()?;
+            let modified = metadata.modified().ok()?;
+            Some((path, metadata.len(), modified))
+        })
+        .collect();
+
+    // Files with a unique size can't have a copy, so only the rest are read
+    let mut sizes: HashMap<u64, usize> = HashMap::new();
+    for (_, size, _) in &stats {
+        *sizes.entry(*size).or_default() += 1;
+    }
+
+    let hashed: Vec<_> = stats
+        .into_iter()
+        .filter(|(_, size, _)| sizes[size] > 1)
+        .filter_map(|(path, size, modified)| {
+            let hash = index.content_hash(&path, modified)?;
+            Some((path, size, hash))
+        })
+        .collect();
+    index.retain_content_hashes(hashed.iter().map(|(path, _, _)| path.as_str()));
+    group_duplicates(hashed)
+}
+
+/// Find indexed papers saved more than once, grouped by identical content.
+///
+/// Read-only; hashes are cached per path and mtime so reruns only read changed files.
+#[tauri::command]
+pub async fn find_duplicate_papers(app: AppHandle) -> Result<Vec<DuplicateGroup>, String> {
+    tauri::async_runtime::spawn_blocking(move || compute_duplicates(&app.state::<FileIndex>()))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// How much of each paper `search_content` reads
+const CONTENT_READ_LIMIT: u64 = 256 * 1024;
+
+/// Most papers `search_content` scans per query
+const CONTENT_SEARCH_MAX_FILES: usize = 5000;
+
+/// Longest snippet returned with a content match, in characters
+const SNIPPET_MAX_CHARS: usize = 160;
+
+/// A paper whose text contains every word of a `search_content` query
+#[derive(Debug, Clone, Serialize)]
+pub struct ContentSearchResult {
+    pub path: String,
+    pub display_path: String,
+    /// Lines containing at least one query word
+    pub hits: usize,
+    /// The line matching the most query words, trimmed
+    pub snippet: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct ContentMatch {
+    hits: usize,
+    score: usize,
+    snippet: String,
+}
+
+/// Score a paper's text against lowercase query words, or None unless it contains
+/// them all.
+///
+/// Each matching line scores the square of the number of words it contains, so
+/// papers with many matches rank high and ones with the words close together (on
+/// the same line) rank higher still.
+fn score_content(content: &str, terms: &[String]) -> Option<ContentMatch> {
+    let mut seen = vec![false; terms.len()];
+    let mut hits = 0;
+    let mut score = 0;
+    let mut best: Option<(usize, &str)> = None;
+
+    for line in content.lines() {
+        let lower = line.to_lowercase();
+        let mut matched = 0;
+        for (term, seen) in terms.iter().zip(seen.iter_mut()) {
+            if lower.contains(term.as_str()) {
+                *seen = true;
+                matched += 1;
+            }
+        }
+        if matched == 0 {
+            continue;
+        }
+
+        hits += 1;
+        score += matched * matched;
+        if best.map_or(true, |(most, _)| matched > most) {
+            best = Some((matched, line));
+        }
+    }
+
+    if !seen.iter().all(|&s| s) {
+        return None;
+    }
+    Some(ContentMatch {
+        hits,
+        score,
+        snippet: snippet(best.map_or("", |(_, line)| line)),
+    })
+}
+
+fn snippet(line: &str) -> String {
+    let line = line.trim();
+    if line.chars().count() <= SNIPPET_MAX_CHARS {
+        return line.to_string();
+    }
+    let mut cut: String = line.chars().take(SNIPPET_MAX_CHARS - 1).collect();
+    cut.push('…');
+    cut
+}
+
+/// The first `CONTENT_READ_LIMIT` bytes of a file as text
+fn read_content(path: &str) -> Option<String> {
+    let mut buf = Vec::new();
+    fs::File::open(path)
+        .ok()?
+        .take(CONTENT_READ_LIMIT)
+        .read_to_end(&mut buf)
+        .ok()?;
+    Some(String::from_utf8_lossy(&buf).into_owned())
+}
+
+/// Search the text of indexed papers for every word of `query` (case-insensitive),
+/// best matches first (see `score_content`).
+///
+/// Reads at most `CONTENT_READ_LIMIT` bytes of each of the first
+/// `CONTENT_SEARCH_MAX_FILES` papers and returns the top 20.
+#[tauri::command]
+pub async fn search_content(
+    app: AppHandle,
+    query: String,
+) -> Result<Vec<ContentSearchResult>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        let index = app.state::<FileIndex>();
+        let home_dir = index.home_dir();
+        let roots = index.get_roots();
+        let mut matches: Vec<(String, ContentMatch)> = index
+            .get_paths()
+            .into_iter()
+            .take(CONTENT_SEARCH_MAX_FILES)
+            .filter_map(|path| {
+                let found = score_content(&read_content(&path)?, &terms)?;
+                Some((path, found))
+            })
+            .collect();
+        matches.sort_by_key(|(_, found)| std::cmp::Reverse(found.score));
+
+        matches
+            .into_iter()
+            .take(20)
+            .map(|(path, found)| ContentSearchResult {
+                display_path: format_display_path(&path, &roots, home_dir),
+                path,
+                hits: found.hits,
+                snippet: found.snippet,
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Whether an executable with this name is on PATH
+fn has_executable(name: &str) -> bool {
+    let Some(path) = env::var_os("PATH") else {
+        return false;
+    };
+    env::split_paths(&path).any(|dir| {
+        let candidate = dir.join(name);
+        candidate.is_file() || candidate.with_extension("exe").is_file()
+    })
+}
+
+/// How many file descriptors this process has open, from `/dev/fd` (Linux and macOS)
+fn open_file_descriptors() -> Option<usize> {
+    // The listing itself holds one open while it's read
+    fs::read_dir("/dev/fd")
+        .ok()
+        .map(|entries| entries.count().saturating_sub(1))
+}
+
+/// Report which indexing backend and search features the host supports.
+///
+/// Only tools the index actually runs are probed: `mdfind` for listing and `rg` for
+/// content search. `fd` isn't, since the fallback walks the tree itself and would
+/// gain nothing from it.
+#[tauri::command]
+pub fn search_capabilities(app: AppHandle) -> SearchCapabilities {
+    let backend = if has_executable("mdfind") {
+        "mdfind"
+    } else {
+        "walk"
+    };
+
+    SearchCapabilities {
+        backend: backend.to_string(),
+        has_content_search: has_executable("rg"),
+        has_watcher: !app.state::<FileIndex>().watching_paused(),
+        open_file_descriptors: open_file_descriptors(),
+    }
+}
+
+/// Refresh the file index in the background.
+///
+/// A request made while a refresh is running folds into one rerun after it, so
+/// bursts of focus events don't list the roots several times over; file commands
+/// run meanwhile apply at once and again on top of the listing.
+#[tauri::command]
+pub fn refresh_file_index(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let index = app.state::<FileIndex>();
+        let Some(mut claim) = index.begin_refresh() else {
+            return;
+        };
+        loop {
+            let started = Instant::now();
+            let paths = match list_index(&app, &index) {
+                Ok(listing) => {
+                    // Installed before `finish` so queued changes reach them too
+                    index.update_collections(listing.collections);
+                    Some(listing.paths)
+                }
+                Err(e) => {
+                    let message = format!("Failed to refresh file index: {}", e);
+                    diagnostics::record(&app, log::Level::Error, "index", message);
+                    None
+                }
+            };
+            let refreshed = paths.is_some();
+            let rerun = claim.finish(paths);
+            if refreshed {
+                index_details(&app, &index, started);
+            }
+            if !rerun {
+                break;
+            }
+        }
+    });
+}
+
+/// Record a finished refresh and fill in the per-file details it doesn't list
+fn index_details(app: &AppHandle, index: &FileIndex, started: Instant) {
+    index.record_refresh_duration(started.elapsed());
+    let message = format!("File index refreshed: {} papers", index.snapshot().len());
+    diagnostics::record(app, log::Level::Info, "index", message);
+
+    // Modification times back `search_recent` and sizes `library_overview`; a stat per
+    // file is cheap next to tags
+    let stats: HashMap<String, (u64, u64)> = index
+        .get_paths()
+        .into_iter()
+        .filter_map(|path| {
+            let metadata = fs::metadata(&path).ok()?;
+            Some((path, (metadata.len(), modified_ms(&metadata))))
+        })
+        .collect();
+
+    // Tags need a read per file, so collect them after paths are searchable, and only
+    // for files modified since their tags were read
+    index.refresh_tags(&stats, |path| frontmatter::read_tags(Path::new(path)));
+    index.update_file_stats(stats);
+}
+
+/// Display paths for raw paths (e.g. from history or sessions), formatted as search results are
+#[tauri::command]
+pub fn format_paths(app: AppHandle, paths: Vec<String>) -> Vec<String> {
+    let index = app.state::<FileIndex>();
+    let home_dir = index.home_dir();
+    let roots = index.get_roots();
+    paths
+        .iter()
+        .map(|path| format_display_path(path, &roots, home_dir))
+        .collect()
+}
+
+/// Timings of the last search and index refresh
+#[tauri::command]
+pub fn perf_stats(app: AppHandle) -> PerfStats {
+    app.state::<FileIndex>().perf_stats()
+}
+
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// Start of the window covering today and the `days` before it, at local midnight.
+///
+/// `utc_offset_ms` gives the local offset at an instant. It's asked again at the
+/// cutoff, so a DST change inside the window doesn't move it off midnight.
+fn recent_cutoff_ms(now_ms: u64, days: u32, utc_offset_ms: impl Fn(u64) -> i64) -> u64 {
+    let day = DAY_MS as i64;
+    let today = (now_ms as i64 + utc_offset_ms(now_ms)).div_euclid(day);
+    let start_local = (today - i64::from(days)) * day;
+    let at = |offset: i64| (start_local - offset).max(0) as u64;
+    at(utc_offset_ms(at(utc_offset_ms(now_ms))))
+}
+
+/// The system's UTC offset at `at_ms`, from the C library's timezone rules
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn local_utc_offset_ms(at_ms: u64) -> i64 {
+    let secs = (at_ms / 1000) as libc::time_t;
+    // SAFETY: localtime_r only writes into the tm we own; all-zero is a valid tm
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&secs, &mut tm) }.is_null() {
+        return 0;
+    }
+    tm.tm_gmtoff as i64 * 1000
+}
+
+/// No timezone lookup on this platform, so days start at UTC midnight
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn local_utc_offset_ms(_at_ms: u64) -> i64 {
+    0
+}
+
+/// Paths modified at or after `since_ms`, newest first
+fn modified_since(modified: &HashMap<String, u64>, since_ms: u64) -> Vec<String> {
+    let mut recent: Vec<(&String, u64)> = modified
+        .iter()
+        .filter(|(_, &ms)| ms >= since_ms)
+        .map(|(path, &ms)| (path, ms))
+        .collect();
+    recent.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    recent.into_iter().map(|(path, _)| path.clone()).collect()
+}
+
+/// Papers modified today or in the last `days` days (0 is today only).
+///
+/// Sorted by recency, or fuzzy-scored against `query` within that window when one is given.
+/// Uses the modification times cached at the last index refresh.
+#[tauri::command]
+pub fn search_recent(app: AppHandle, days: u32, query: Option<String>) -> Vec<FileSearchResult> {
+    let index = app.state::<FileIndex>();
+    let home_dir = index.home_dir();
+    let roots = index.get_roots();
+
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64);
+    let cutoff = recent_cutoff_ms(now_ms, days, local_utc_offset_ms);
+    let recent = modified_since(&index.modified.read().unwrap(), cutoff);
+
+    let results = match query.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        Some(query) => {
+            let mut scored = score_paths(&recent, query, home_dir, 0, index.matcher_settings());
+            scored.sort_by_key(|r| std::cmp::Reverse(r.1));
+            scored
+        }
+        None => recent.into_iter().map(|path| (path, 0)).collect(),
+    };
+
+    results
+        .into_iter()
+        .take(20)
+        .map(|(path, score)| FileSearchResult::new(path, score, &roots, home_dir))
+        .collect()
+}
+
+/// Find all indexed papers whose front matter carries the tag
+#[tauri::command]
+pub fn search_by_tag(app: AppHandle, tag: String) -> Vec<FileSearchResult> {
+    let index = app.state::<FileIndex>();
+    let home_dir = index.home_dir();
+    let roots = index.get_roots();
+
+    index
+        .paths_with_tag(tag.trim())
+        .into_iter()
+        .map(|path| FileSearchResult::new(path, 0, &roots, home_dir))
+        .collect()
+}
+
+/// List every tag with its paper count, for a tag cloud
+#[tauri::command]
+pub fn list_all_tags(app: AppHandle) -> Vec<(String, usize)> {
+    app.state::<FileIndex>().tag_counts()
+}
+
+/// Refresh the file index if it's stale (called on window focus), unless watching
+/// is paused. A frozen index is only refreshed while it's still empty.
+pub fn refresh_if_stale(app: &AppHandle, threshold_secs: u64) {
+    if app.state::<FileIndex>().wants_refresh(threshold_secs) {
+        refresh_file_index(app.clone());
+    }
+}
+
+#[tauri::command]
+pub fn get_index_frozen(app: AppHandle) -> bool {
+    app.state::<FileIndex>().is_frozen()
+}
+
+/// Keep the current index through a reading session: refreshes on focus (and the
+/// mdfind spawn they cost) are skipped while search goes on against the current
+/// snapshot. Not persisted; unfreezing refreshes once.
+#[tauri::command]
+pub fn freeze_index(app: AppHandle, frozen: bool) {
+    let was_frozen = app.state::<FileIndex>().set_frozen(frozen);
+    if was_frozen && !frozen {
+        refresh_file_index(app);
+    }
+}
+
+/// Stop refreshing the index automatically (on focus, or retrying an empty index),
+/// e.g. while a script reorganizes many files. Not persisted; explicit refreshes
+/// still run.
+#[tauri::command]
+pub fn pause_file_watching(app: AppHandle) {
+    app.state::<FileIndex>().set_watching_paused(true);
+}
+
+/// Resume automatic refreshes, refreshing once to catch up on what changed meanwhile
+#[tauri::command]
+pub fn resume_file_watching(app: AppHandle) {
+    if app.state::<FileIndex>().set_watching_paused(false) {
+        refresh_file_index(app);
+    }
+}
+
+/// Minimum score a query of this length should reach when filtering is enabled.
+///
+/// nucleo awards 16 points per matched character plus bonuses for consecutive
+/// characters and word/path boundaries, minus penalties for gaps. A contiguous
+/// match of an n-character query scores around 25n while one scattered across
+/// unrelated words lands nearer 18n, so the floor sits at 20n.
+pub fn score_floor(query: &str) -> u16 {
+    let len = query.trim().chars().count().min(u16::MAX as usize / 20) as u16;
+    len * 20
+}
+
+/// Fuzzy scorer for one query, shared by searching and counting
+struct PathScorer {
+    matcher: Matcher,
+    atom: Atom,
+    buf: Vec<char>,
+}
+
+impl PathScorer {
+    fn new(query: &str, settings: MatcherSettings) -> Self {
+        // Create matcher and pattern
+        let matcher = Matcher::new(settings.config());
+        let atom = Atom::new(
+            query,
+            settings.case_mode.into(),
+            Normalization::Smart,
+            AtomKind::Fuzzy,
+            false,
+        );
+        Self {
+            matcher,
+            atom,
+            buf: Vec::new(),
+        }
+    }
+
+    fn score(&mut self, path: &str, home_dir: &str) -> Option<u16> {
+        // Match against the path without home prefix for better UX
+        let match_target = path.strip_prefix(home_dir).unwrap_or(path);
+
+        // Convert to UTF-32 for nucleo
+        let haystack_str = Utf32Str::new(match_target, &mut self.buf);
+
+        self.atom.score(haystack_str, &mut self.matcher)
+    }
+}
+
+/// The directory a query drills into, if it ends in `/` (`ml/`, `~/papers/ml/`)
+fn directory_query(query: &str) -> Option<&str> {
+    query
+        .trim()
+        .strip_suffix('/')
+        .map(|dir| dir.trim_end_matches('/'))
+        .filter(|dir| !dir.is_empty() && *dir != "~")
+}
+
+/// Paths under a directory query, sorted by file name.
+///
+/// A query starting with `/` or `~/` must match from the start of the path (or the
+/// home directory); otherwise it may match at any folder. Folders match as whole
+/// path components (`ml/` doesn't match `ml-notes/`). Case-insensitive.
+fn paths_under_directory(files: &[String], dir: &str, home_dir: &str) -> Vec<String> {
+    let dir = dir.to_lowercase();
+    let (needle, anchored) = match dir.strip_prefix("~/") {
+        Some(rest) => (format!("/{}/", rest), true),
+        None if dir.starts_with('/') => (format!("{}/", dir), true),
+        None => (format!("/{}/", dir), false),
+    };
+
+    let mut matches: Vec<String> = files
+        .iter()
+        .filter(|path| {
+            let target = if anchored && dir.starts_with('/') {
+                path.as_str()
+            } else {
+                path.strip_prefix(home_dir).unwrap_or(path)
+            };
+            let folder = Path::new(target)
+                .parent()
+                .map(|p| format!("{}/", p.to_string_lossy().to_lowercase()))
+                .unwrap_or_default();
+            if anchored {
+                folder.starts_with(&needle)
+            } else {
+                folder.contains(&needle)
+            }
+        })
+        .cloned()
+        .collect();
+
+    let name = |path: &str| Path::new(path).file_name().map(|n| n.to_ascii_lowercase());
+    matches.sort_by(|a, b| name(a).cmp(&name(b)).then_with(|| a.cmp(b)));
+    matches
+}
+
+/// The paths a query is scored against: all of `files`, or just `pinned` (favorites
+/// and recents) for queries under `MIN_FULL_SCAN_QUERY_CHARS`
+fn search_candidates(
+    files: Arc<Vec<String>>,
+    query: &str,
+    pinned: impl FnOnce() -> Vec<String>,
+) -> Arc<Vec<String>> {
+    if query.trim().chars().count() >= MIN_FULL_SCAN_QUERY_CHARS {
+        return files;
+    }
+    let mut seen = HashSet::new();
+    let pinned = pinned()
+        .into_iter()
+        .filter(|path| seen.insert(path.clone()));
+    Arc::new(pinned.collect())
+}
+
+/// Favorites then recents, within a collection when one is given
+fn pinned_paths(app: &AppHandle, index: &FileIndex, collection: Option<&str>) -> Vec<String> {
+    let pinned = library::favorites(app)
+        .into_iter()
+        .chain(library::recents(app));
+    index.within_collection(pinned.collect(), collection)
+}
+
+/// Score each path against the query, keeping matches that reach `min_score`.
+fn score_paths(
+    files: &[String],
+    query: &str,
+    home_dir: &str,
+    min_score: u16,
+    settings: MatcherSettings,
+) -> Vec<(String, u16)> {
+    let mut scorer = PathScorer::new(query, settings);
+    files
+        .iter()
+        .filter_map(|path| {
+            scorer
+                .score(path, home_dir)
+                .filter(|&score| score >= min_score)
+                .map(|score| (path.clone(), score))
+        })
+        .collect()
+}
+
+/// Boost the scores of paths whose file name (without extension) starts with the
+/// query, case-insensitively
+fn boost_filename_prefixes(scored: &mut [(String, u16)], query: &str) {
+    let query = query.trim().to_lowercase();
+    for (path, score) in scored.iter_mut() {
+        let is_prefix = Path::new(path)
+            .file_stem()
+            .is_some_and(|stem| stem.to_string_lossy().to_lowercase().starts_with(&query));
+        if is_prefix {
+            *score = score.saturating_add(FILENAME_PREFIX_BOOST);
+        }
+    }
+}
+
+/// Boost the scores of recently opened papers (`opened` maps paths to when they
+/// were last opened), more the more recently they were opened
+fn boost_recently_opened(
+    scored: &mut [(String, u16)],
+    opened: &HashMap<String, u64>,
+    now_ms: u64,
+    settings: MatcherSettings,
+) {
+    let window_ms = u64::from(settings.recency_window_days) * DAY_MS;
+    if settings.recency_boost == 0 || window_ms == 0 {
+        return;
+    }
+
+    for (path, score) in scored.iter_mut() {
+        let Some(&opened_ms) = opened.get(path) else {
+            continue;
+        };
+        let age_ms = now_ms.saturating_sub(opened_ms);
+        if age_ms < window_ms {
+            let bonus = u64::from(settings.recency_boost) * (window_ms - age_ms) / window_ms;
+            *score = score.saturating_add(bonus as u16);
+        }
+    }
+}
+
+/// Sort scored paths best first, with favorites ahead of everything else
+fn rank_favorites_first(scored: &mut [(String, u16)], favorites: &HashSet<String>) {
+    scored.sort_by_key(|(path, score)| (!favorites.contains(path), std::cmp::Reverse(*score)));
+}
+
+/// Count paths matching the query without sorting or building results
+fn count_path_matches(
+    files: &[String],
+    query: &str,
+    home_dir: &str,
+    settings: MatcherSettings,
+) -> usize {
+    let mut scorer = PathScorer::new(query, settings);
+    files
+        .iter()
+        .filter(|path| scorer.score(path, home_dir).is_some())
+        .count()
+}
+
+/// Count indexed files matching the query (all files for an empty query).
+///
+/// Like `search_files`, short queries only count matching favorites and recents.
+#[tauri::command]
+pub fn count_matches(app: AppHandle, query: String) -> usize {
+    let index = app.state::<FileIndex>();
+    let files = index.snapshot();
+    if query.trim().is_empty() {
+        return files.len();
+    }
+
+    let home_dir = index.home_dir();
+    let settings = index.matcher_settings();
+    match directory_query(&query) {
+        Some(dir) if !settings.fuzzy_directory_queries => {
+            paths_under_directory(&files, dir, home_dir).len()
+        }
+        _ => {
+            let files = search_candidates(files, &query, || pinned_paths(&app, &index, None));
+            count_path_matches(&files, &query, home_dir, settings)
+        }
+    }
+}
+
+/// Fuzzy search a caller-provided list of paths instead of the index, for a second
+/// stage after filtering by folder or tag (e.g. from `search_by_tag`).
+///
+/// Candidates are scored as given, whether or not they're indexed. Ranked like
+/// `search_files`, returning the top 20; an empty query returns the first 20 in order.
+#[tauri::command]
+pub fn search_in(app: AppHandle, query: String, candidates: Vec<String>) -> Vec<FileSearchResult> {
+    let index = app.state::<FileIndex>();
+    let home_dir = index.home_dir();
+    let roots = index.all_roots();
+
+    let scored = if query.trim().is_empty() {
+        candidates.into_iter().map(|path| (path, 0)).collect()
+    } else {
+        let mut scored = score_paths(&candidates, &query, home_dir, 0, index.matcher_settings());
+        boost_filename_prefixes(&mut scored, &query);
+        scored.sort_by_key(|r| std::cmp::Reverse(r.1));
+        scored
+    };
+
+    scored
+        .into_iter()
+        .take(20)
+        .map(|(path, score)| FileSearchResult::new(path, score, &roots, home_dir))
+        .collect()
+}
+
+/// Perform fuzzy search on cached file paths.
+///
+/// `min_score` drops weak matches before the top 20 are taken. When set, the
+/// threshold is raised to at least `score_floor(query)` so longer queries demand
+/// stronger matches. Defaults to 0 (no filtering).
+///
+/// `exclude_open` also drops papers already open in a tab before the top 20 are taken.
+///
+/// A query ending in `/` lists every paper under the matching directory by name
+/// instead (see `paths_under_directory`), unless `fuzzy_directory_queries` is set.
+///
+/// `collection` limits the search to one named collection (see
+/// `set_collection_roots`); None searches everything indexed.
+///
+/// Favorites come first in every case, flagged with `is_favorite`.
+#[tauri::command]
+pub fn search_files(
+    app: AppHandle,
+    query: String,
+    min_score: Option<u16>,
+    scope: Option<SearchScope>,
+    exclude_open: Option<bool>,
+    collection: Option<String>,
+) -> Vec<FileSearchResult> {
+    let index = app.state::<FileIndex>();
+    let home_dir = index.home_dir();
+    let collection = collection.as_deref();
+    let scope = scope.unwrap_or_default();
+    let files = match (scope, collection) {
+        (SearchScope::All, None) => index.snapshot(),
+        (SearchScope::All, Some(name)) => index.collection_snapshot(name).unwrap_or_default(),
+        (SearchScope::Favorites, name) => {
+            Arc::new(index.within_collection(library::favorites(&app), name))
+        }
+        (SearchScope::Recents, name) => {
+            Arc::new(index.within_collection(library::recents(&app), name))
+        }
+    };
+    let roots = index.all_roots();
+    let open = if exclude_open.unwrap_or(false) {
+        app.state::<TabManager>().open_paper_paths()
+    } else {
+        HashSet::new()
+    };
+    // Lazy, so only candidates up to the top 20 are canonicalized
+    let is_shown = |path: &String| open.is_empty() || !open.contains(&canonical_path(path));
+    let favorites: HashSet<String> = library::favorites(&app).into_iter().collect();
+    let is_favorite = |path: &&String| favorites.contains(*path);
+
+    // Persisted only once the search opens a paper (see `save_last_query`)
+    if !query.trim().is_empty() {
+        app.state::<LastQuery>().set(&query);
+    }
+
+    // If query is empty, return the first 20 files (the scope's members, in its
+    // own order) after any favorites among them
+    if query.trim().is_empty() {
+        let others = files.iter().filter(|path| !is_favorite(path));
+        return files
+            .iter()
+            .filter(is_favorite)
+            .chain(others)
+            .filter(|path| is_shown(path))
+            .take(20)
+            .map(|path| FileSearchResult::new(path.clone(), 0, &roots, home_dir))
+            .map(|result| result.mark_favorite(&favorites))
+            .collect();
+    }
+
+    let settings = index.matcher_settings();
+    if let Some(dir) = directory_query(&query).filter(|_| !settings.fuzzy_directory_queries) {
+        let mut listed = paths_under_directory(&files, dir, home_dir);
+        listed.sort_by_key(|path| !favorites.contains(path));
+        return listed
+            .into_iter()
+            .filter(is_shown)
+            .take(20)
+            .map(|path| FileSearchResult::new(path, 0, &roots, home_dir))
+            .map(|result| result.mark_favorite(&favorites))
+            .collect();
+    }
+
+    let threshold = match min_score {
+        Some(min) if min > 0 => min.max(score_floor(&query)),
+        _ => 0,
+    };
+
+    // Score each file path (only favorites and recents for one-character queries)
+    let started = Instant::now();
+    let files = match scope {
+        SearchScope::All => {
+            search_candidates(files, &query, || pinned_paths(&app, &index, collection))
+        }
+        SearchScope::Favorites | SearchScope::Recents => files,
+    };
+    let mut scored_results = score_paths(&files, &query, home_dir, threshold, settings);
+    boost_filename_prefixes(&mut scored_results, &query);
+    if settings.recency_boost > 0 {
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64);
+        let opened = library::recent_open_times(&app);
+        boost_recently_opened(&mut scored_results, &opened, now_ms, settings);
+    }
+
+    // Favorites first, then by score descending
+    rank_favorites_first(&mut scored_results, &favorites);
+    index.record_search_duration(started.elapsed());
+
+    // Take top 20 and convert to result format
+    scored_results
+        .into_iter()
+        .filter(|(path, _)| is_shown(path))
+        .take(20)
+        .map(|(path, score)| FileSearchResult::new(path, score, &roots, home_dir))
+        .map(|result| result.mark_favorite(&favorites))
+        .collect()
+}
+
+/// `search_files`, but reporting an empty index separately from a query that
+/// matched nothing, so the UI can show "indexing" rather than "no results".
+///
+/// An empty index also kicks off a refresh, at most once every few seconds.
+#[tauri::command]
+pub fn search_files_detailed(
+    app: AppHandle,
+    query: String,
+    min_score: Option<u16>,
+    scope: Option<SearchScope>,
+    exclude_open: Option<bool>,
+    collection: Option<String>,
+) -> SearchResponse {
+    let index = app.state::<FileIndex>();
+    let index_empty = index.is_empty();
+    if index_empty && index.is_stale(EMPTY_INDEX_REFRESH_SECS) && !index.watching_paused() {
+        refresh_file_index(app.clone());
+    }
+
+    SearchResponse {
+        results: search_files(
+            app.clone(),
+            query,
+            min_score,
+            scope,
+            exclude_open,
+            collection,
+        ),
+        index_empty,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_path_replaces_home_prefix() {
+        assert_eq!(
+            to_display_path("/Users/me/notes/a.md", "/Users/me"),
+            "~/notes/a.md"
+        );
+    }
+
+    #[test]
+    fn context_shows_up_to_two_parent_folders() {
+        let home = "/Users/me";
+        assert_eq!(
+            parent_context("/Users/me/papers/ml/transformers/notes.md", home),
+            "ml/transformers"
+        );
+        assert_eq!(parent_context("/Users/me/ml/notes.md", home), "ml");
+        assert_eq!(parent_context("/Users/me/notes.md", home), "");
+        assert_eq!(parent_context("/srv/notes.md", home), "srv");
+    }
+
+    #[test]
+    fn display_path_handles_trailing_slash_on_home() {
+        assert_eq!(
+            to_display_path("/Users/me/notes/a.md", "/Users/me/"),
+            "~/notes/a.md"
+        );
+    }
+
+    #[test]
+    fn display_path_ignores_partial_component_match() {
+        assert_eq!(
+            to_display_path("/Users/mel/notes/a.md", "/Users/me"),
+            "/Users/mel/notes/a.md"
+        );
+    }
+
+    #[test]
+    fn display_path_leaves_paths_outside_home() {
+        assert_eq!(to_display_path("/tmp/a.md", "/Users/me"), "/tmp/a.md");
+        assert_eq!(to_display_path("/tmp/a.md", ""), "/tmp/a.md");
+    }
+
+    #[test]
+    fn score_floor_grows_with_query_length() {
+        assert_eq!(score_floor(""), 0);
+        assert!(score_floor("attention") > score_floor("att"));
+    }
+
+    #[test]
+    fn min_score_drops_weak_matches() {
+        let files = vec![
+            "/home/me/papers/attention.md".to_string(),
+            "/home/me/archive/tax-returns/2020/entries/notion.md".to_string(),
+        ];
+
+        let settings = MatcherSettings::default();
+        let all = score_paths(&files, "attention", "/home/me", 0, settings);
+        assert_eq!(all.len(), 2);
+
+        let floor = score_floor("attention");
+        let strong = score_paths(&files, "attention", "/home/me", floor, settings);
+        assert_eq!(strong.len(), 1);
+        assert_eq!(strong[0].0, "/home/me/papers/attention.md");
+    }
+
+    #[test]
+    fn dedupe_keeps_first_occurrence_in_order() {
+        let paths = vec![
+            "/Users/me/Documents/a.md".to_string(),
+            "/Users/me/b.md".to_string(),
+            "/Users/me/Documents/a.md".to_string(),
+        ];
+        assert_eq!(
+            dedupe_paths(paths, &mut HashMap::new(), &mut HashMap::new()),
+            vec!["/Users/me/Documents/a.md", "/Users/me/b.md"]
+        );
+    }
+
+    #[test]
+    fn dedupe_reuses_canonical_forms_until_the_file_changes() {
+        let dir = std::env::temp_dir().join(format!("papers-dedupe-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let a = dir.join("a.md");
+        let b = dir.join("b.md");
+        fs::write(&a, "a").unwrap();
+        fs::write(&b, "b").unwrap();
+        let (a, b) = (
+            a.to_string_lossy().to_string(),
+            b.to_string_lossy().to_string(),
+        );
+
+        let dedupe = |paths: Vec<String>, cache: &mut HashMap<_, _>| {
+            let mut resolved = HashMap::new();
+            let deduped = dedupe_paths(paths, cache, &mut resolved);
+            *cache = resolved;
+            deduped
+        };
+        let mut cache = HashMap::new();
+        let paths = vec![a.clone(), b.clone()];
+        assert_eq!(dedupe(paths.clone(), &mut cache), paths);
+        assert_eq!(cache[&a].1, canonical_path(&a));
+
+        // A cached form is trusted while the mtime matches, so b now reads as a duplicate
+        let a_canonical = cache[&a].1.clone();
+        cache.get_mut(&b).unwrap().1 = a_canonical;
+        assert_eq!(dedupe(paths.clone(), &mut cache), vec![a.clone()]);
+
+        // ...and resolved again once it changes
+        cache.get_mut(&b).unwrap().0 += 1;
+        assert_eq!(dedupe(paths.clone(), &mut cache), paths);
+
+        // Paths no longer listed are dropped from the cache
+        dedupe(vec![b.clone()], &mut cache);
+        assert_eq!(cache.keys().collect::<Vec<_>>(), vec![&b]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn display_path_uses_most_specific_root() {
+        let home = "/Users/me";
+        let roots = vec!["/Users/me".to_string(), "/Users/me/Documents/".to_string()];
+
+        assert_eq!(
+            format_display_path("/Users/me/Documents/ml/a.md", &roots, home),
+            "Documents/ml/a.md"
+        );
+        assert_eq!(
+            format_display_path("/Users/me/notes/b.md", &roots, home),
+            "~/notes/b.md"
+        );
+        assert_eq!(format_display_path("/tmp/c.md", &roots, home), "/tmp/c.md");
+    }
+
+    #[test]
+    fn count_matches_agrees_with_scoring() {
+        let files = vec![
+            "/home/me/papers/attention.md".to_string(),
+            "/home/me/notes/transformer.md".to_string(),
+            "/home/me/recipes/soup.md".to_string(),
+        ];
+        let settings = MatcherSettings::default();
+        let scored = score_paths(&files, "tion", "/home/me", 0, settings);
+        assert_eq!(
+            count_path_matches(&files, "tion", "/home/me", settings),
+            scored.len()
+        );
+    }
+
+    #[test]
+    fn tag_counts_sort_by_frequency_then_name() {
+        let mut tags = HashMap::new();
+        tags.insert(
+            "a.md".to_string(),
+            vec!["nlp".to_string(), "ml".to_string()],
+        );
+        tags.insert("b.md".to_string(), vec!["ML".to_string(), "Ml".to_string()]);
+        tags.insert("c.md".to_string(), vec!["cv".to_string()]);
+
+        assert_eq!(
+            count_tags(&tags),
+            vec![
+                ("ml".to_string(), 2),
+                ("cv".to_string(), 1),
+                ("nlp".to_string(), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn tree_nests_folders_before_papers() {
+        let paths = vec![
+            "/home/me/zeta.md".to_string(),
+            "/home/me/ml/b.md".to_string(),
+            "/home/me/ml/a.md".to_string(),
+            "/home/me/ml/nlp/c.md".to_string(),
+            "/elsewhere/d.md".to_string(),
+        ];
+        let roots = vec!["/home/me".to_string()];
+        let tree = build_tree("/home/me", &paths, &roots, "/home/me");
+
+        assert_eq!(tree.name, "~");
+        let names: Vec<_> = tree.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["ml", "zeta.md"]);
+
+        let ml = &tree.children[0];
+        assert_eq!(ml.path, "/home/me/ml");
+        let names: Vec<_> = ml.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["nlp", "a.md", "b.md"]);
+        assert_eq!(ml.children[1].display_path.as_deref(), Some("~/ml/a.md"));
+    }
+
+    #[test]
+    fn month_of_formats_utc_months() {
+        assert_eq!(month_of(0), "1970-01");
+        // 2024-02-29T12:00:00Z
+        assert_eq!(month_of(1_709_208_000_000), "2024-02");
+        // 2023-12-31T23:59:59Z
+        assert_eq!(month_of(1_704_067_199_000), "2023-12");
+    }
+
+    #[test]
+    fn summarize_totals_and_groups_by_month() {
+        let jan = 1_704_067_200_000; // 2024-01-01
+        let mar = 1_709_251_200_000; // 2024-03-01
+        let overview = summarize(vec![(10, mar), (5, jan), (1, jan)]);
+
+        assert_eq!(overview.total_files, 3);
+        assert_eq!(overview.total_bytes, 16);
+        assert_eq!(overview.oldest_ms, Some(jan));
+        assert_eq!(overview.newest_ms, Some(mar));
+        assert_eq!(
+            overview.by_month,
+            vec![("2024-01".to_string(), 2), ("2024-03".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn duplicates_group_by_hash_and_skip_unique_files() {
+        let entry = |path: &str, hash: &str| (path.to_string(), 10, hash.to_string());
+        let groups = group_duplicates(vec![
+            entry("/b/a.md", "h1"),
+            entry("/a/a.md", "h1"),
+            entry("/c.md", "h2"),
+        ]);
+
+        assert_eq!(
+            groups,
+            vec![DuplicateGroup {
+                hash: "h1".to_string(),
+                size: 10,
+                paths: vec!["/a/a.md".to_string(), "/b/a.md".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn default_matcher_settings_rank_by_path_boundaries() {
+        let ranked = |files: &[&str], query: &str| {
+            let files: Vec<String> = files.iter().map(|f| f.to_string()).collect();
+            let mut scored = score_paths(&files, query, "/home/me", 0, MatcherSettings::default());
+            scored.sort_by_key(|r| std::cmp::Reverse(r.1));
+            scored.into_iter().map(|(path, _)| path).collect::<Vec<_>>()
+        };
+
+        // A contiguous match beats one scattered over dashes
+        assert_eq!(
+            ranked(
+                &[
+                    "/home/me/notes/at-ten-tion.md",
+                    "/home/me/papers/attention.md"
+                ],
+                "atten"
+            ),
+            vec![
+                "/home/me/papers/attention.md",
+                "/home/me/notes/at-ten-tion.md"
+            ]
+        );
+        // The start of a folder outranks a word after a space
+        assert_eq!(
+            ranked(
+                &["/home/me/deep learning/x.md", "/home/me/deep/learning/x.md"],
+                "learn"
+            ),
+            vec!["/home/me/deep/learning/x.md", "/home/me/deep learning/x.md"]
+        );
+        // A lowercase query matches any case
+        assert_eq!(
+            ranked(&["/home/me/ml/Attention.md"], "attention"),
+            vec!["/home/me/ml/Attention.md"]
+        );
+    }
+
+    #[test]
+    fn path_matching_prefers_folder_boundaries_over_spaces() {
+        let files = vec![
+            "/home/me/papers/reading notes.md".to_string(),
+            "/home/me/papers/notes.md".to_string(),
+        ];
+        let best = |match_paths| {
+            let settings = MatcherSettings {
+                match_paths,
+                ..MatcherSettings::default()
+            };
+            let mut scored = score_paths(&files, "notes", "/home/me", 0, settings);
+            scored.sort_by_key(|r| std::cmp::Reverse(r.1));
+            scored[0].0.clone()
+        };
+        assert_eq!(best(true), "/home/me/papers/notes.md");
+        assert_eq!(best(false), "/home/me/papers/reading notes.md");
+
+        // Older stored settings without the field get the new default
+        let stored: MatcherSettings = serde_json::from_str(r#"{"prefer_prefix":true}"#).unwrap();
+        assert!(stored.match_paths && stored.prefer_prefix);
+    }
+
+    #[test]
+    fn filename_prefix_matches_rank_first() {
+        let files = vec![
+            "/home/me/attention/deep/learning/survey.md".to_string(),
+            "/home/me/papers/ml/atten.md".to_string(),
+        ];
+        let settings = MatcherSettings {
+            prefer_prefix: true,
+            ..MatcherSettings::default()
+        };
+        let mut scored = score_paths(&files, "atten", "/home/me", 0, settings);
+        // The longer path matches earlier, so it wins on fuzzy score alone
+        assert!(scored[0].1 > scored[1].1);
+
+        boost_filename_prefixes(&mut scored, "Atten");
+        scored.sort_by_key(|r| std::cmp::Reverse(r.1));
+        assert_eq!(scored[0].0, "/home/me/papers/ml/atten.md");
+    }
+
+    #[test]
+    fn recently_opened_papers_outrank_slightly_better_matches() {
+        let files = vec![
+            "/home/me/papers/attn-survey.md".to_string(),
+            "/home/me/notes/attention.md".to_string(),
+        ];
+        let mut settings = MatcherSettings::default();
+        let mut scored = score_paths(&files, "attn", "/home/me", 0, settings);
+        scored.sort_by_key(|r| std::cmp::Reverse(r.1));
+        assert_eq!(scored[0].0, "/home/me/papers/attn-survey.md");
+        let margin = scored[0].1 - scored[1].1;
+
+        let now_ms = 30 * DAY_MS;
+        let opened = HashMap::from([
+            ("/home/me/notes/attention.md".to_string(), now_ms - DAY_MS),
+            (
+                "/home/me/papers/attn-survey.md".to_string(),
+                now_ms - 8 * DAY_MS,
+            ),
+        ]);
+
+        // Off by default
+        let unboosted = scored.clone();
+        boost_recently_opened(&mut scored, &opened, now_ms, settings);
+        assert_eq!(scored, unboosted);
+
+        settings.recency_boost = margin * 2;
+        settings.recency_window_days = 7;
+        boost_recently_opened(&mut scored, &opened, now_ms, settings);
+        scored.sort_by_key(|r| std::cmp::Reverse(r.1));
+        assert_eq!(scored[0].0, "/home/me/notes/attention.md");
+        // Opened outside the window, so not boosted
+        assert_eq!(scored[1], unboosted[0]);
+    }
+
+    #[test]
+    fn favorites_rank_above_better_matches() {
+        let mut scored = vec![
+            ("/p/a.md".to_string(), 300),
+            ("/p/fav-low.md".to_string(), 10),
+            ("/p/b.md".to_string(), 200),
+            ("/p/fav-high.md".to_string(), 50),
+        ];
+        let favorites = HashSet::from(["/p/fav-low.md".to_string(), "/p/fav-high.md".to_string()]);
+        rank_favorites_first(&mut scored, &favorites);
+
+        let order: Vec<&str> = scored.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(
+            order,
+            vec!["/p/fav-high.md", "/p/fav-low.md", "/p/a.md", "/p/b.md"]
+        );
+    }
+
+    #[test]
+    fn case_mode_controls_uppercase_queries() {
+        let matches = |query: &str, case_mode| {
+            let settings = MatcherSettings {
+                case_mode,
+                ..MatcherSettings::default()
+            };
+            let files = vec!["/home/me/readme.md".to_string()];
+            !score_paths(&files, query, "/home/me", 0, settings).is_empty()
+        };
+
+        assert!(!matches("README", CaseMode::Smart));
+        assert!(matches("README", CaseMode::Insensitive));
+        assert!(!matches("ReadMe", CaseMode::Sensitive));
+        assert!(matches("readme", CaseMode::Sensitive));
+    }
+
+    #[test]
+    fn directory_query_needs_a_trailing_slash() {
+        assert_eq!(directory_query(" ml/ "), Some("ml"));
+        assert_eq!(directory_query("~/papers//"), Some("~/papers"));
+        assert_eq!(directory_query("ml"), None);
+        assert_eq!(directory_query("/"), None);
+        assert_eq!(directory_query("~/"), None);
+    }
+
+    #[test]
+    fn paths_under_directory_match_whole_folders_sorted_by_name() {
+        let files = vec![
+            "/home/me/papers/ml/z.md".to_string(),
+            "/home/me/papers/ml/deep/a.md".to_string(),
+            "/home/me/papers/ml-notes/c.md".to_string(),
+            "/home/me/papers/html/b.md".to_string(),
+            "/home/me/ml.md".to_string(),
+        ];
+
+        let under_ml = vec!["/home/me/papers/ml/deep/a.md", "/home/me/papers/ml/z.md"];
+        assert_eq!(paths_under_directory(&files, "ML", "/home/me"), under_ml);
+        assert_eq!(
+            paths_under_directory(&files, "~/papers/ml", "/home/me"),
+            under_ml
+        );
+        assert!(paths_under_directory(&files, "~/ml", "/home/me").is_empty());
+        assert!(paths_under_directory(&files, "~/papers/m", "/home/me").is_empty());
+        assert_eq!(
+            paths_under_directory(&files, "/home/me/papers/html", "/home/me"),
+            vec!["/home/me/papers/html/b.md"]
+        );
+    }
+
+    #[test]
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    fn open_file_descriptors_include_the_standard_streams() {
+        assert!(open_file_descriptors().unwrap() >= 3);
+    }
+
+    #[test]
+    fn recent_window_starts_at_local_midnight() {
+        const HOUR_MS: u64 = 60 * 60 * 1000;
+        let utc = |_| 0;
+        let noon_day_3 = 3 * DAY_MS + DAY_MS / 2;
+        assert_eq!(recent_cutoff_ms(noon_day_3, 0, utc), 3 * DAY_MS);
+        assert_eq!(recent_cutoff_ms(noon_day_3, 2, utc), DAY_MS);
+        assert_eq!(recent_cutoff_ms(noon_day_3, 10, utc), 0);
+
+        // UTC+8: 20:00 UTC on day 3 is already 04:00 on day 4 locally
+        let east = |_| 8 * HOUR_MS as i64;
+        let evening_day_3 = 3 * DAY_MS + 20 * HOUR_MS;
+        assert_eq!(
+            recent_cutoff_ms(evening_day_3, 0, east),
+            3 * DAY_MS + 16 * HOUR_MS
+        );
+        assert_eq!(
+            recent_cutoff_ms(evening_day_3, 1, east),
+            2 * DAY_MS + 16 * HOUR_MS
+        );
+
+        // UTC-5: 02:00 UTC on day 3 is still 21:00 on day 2 locally
+        let west = |_| -5 * HOUR_MS as i64;
+        let early_day_3 = 3 * DAY_MS + 2 * HOUR_MS;
+        assert_eq!(
+            recent_cutoff_ms(early_day_3, 0, west),
+            2 * DAY_MS + 5 * HOUR_MS
+        );
+
+        // UTC+1 from day 2 on: day 3 starts at 23:00 UTC, day 1 still at 00:00 UTC
+        let dst = |at: u64| if at >= 2 * DAY_MS { HOUR_MS as i64 } else { 0 };
+        assert_eq!(recent_cutoff_ms(noon_day_3, 0, dst), 3 * DAY_MS - HOUR_MS);
+        assert_eq!(recent_cutoff_ms(noon_day_3, 2, dst), DAY_MS);
+    }
+
+    #[test]
+    fn modified_since_sorts_newest_first() {
+        let modified = HashMap::from([
+            ("/old.md".to_string(), 10),
+            ("/new.md".to_string(), 300),
+            ("/mid.md".to_string(), 200),
+        ]);
+        assert_eq!(modified_since(&modified, 100), vec!["/new.md", "/mid.md"]);
+    }
+
+    #[test]
+    fn changes_during_a_refresh_are_replayed_on_its_result() {
+        let index = FileIndex::new();
+        index.update(vec!["/p/a.md".into(), "/p/old.md".into()]);
+        let mut claim = index.begin_refresh().unwrap();
+
+        // Searches see changes at once, even mid-refresh
+        index.add_path("/p/new.md");
+        index.remove_path("/p/old.md");
+        assert_eq!(index.get_paths(), vec!["/p/a.md", "/p/new.md"]);
+
+        // The listing saw old.md before it was trashed and missed new.md
+        let listed = vec!["/p/a.md".into(), "/p/old.md".into(), "/p/b.md".into()];
+        assert!(!claim.finish(Some(listed)));
+        drop(claim);
+        assert_eq!(index.get_paths(), vec!["/p/a.md", "/p/b.md", "/p/new.md"]);
+
+        // With no refresh in flight, changes apply straight away
+        index.remove_path("/p/b.md");
+        assert_eq!(index.get_paths(), vec!["/p/a.md", "/p/new.md"]);
+        assert!(index.begin_refresh().is_some());
+    }
+
+    #[test]
+    fn refreshes_requested_mid_refresh_rerun_it() {
+        let index = FileIndex::new();
+        let mut claim = index.begin_refresh().unwrap();
+        assert!(index.begin_refresh().is_none());
+        assert!(index.begin_refresh().is_none());
+
+        // Both requests fold into one rerun, which keeps the claim
+        assert!(claim.finish(None));
+        assert!(index.begin_refresh().is_none());
+        assert!(claim.finish(None));
+        assert!(!claim.finish(None));
+        drop(claim);
+        assert!(index.begin_refresh().is_some());
+    }
+
+    #[test]
+    fn abandoned_refreshes_release_the_claim() {
+        let index = FileIndex::new();
+        let claim = index.begin_refresh().unwrap();
+        assert!(index.begin_refresh().is_none());
+        drop(claim);
+
+        let claim = index.begin_refresh();
+        assert!(claim.is_some());
+    }
+
+    #[test]
+    fn collections_follow_file_changes() {
+        let index = FileIndex::new();
+        index.set_collection_roots("blog", vec!["/blog".into()]);
+        let listed = HashMap::from([("blog".to_string(), vec!["/blog/a.md".to_string()])]);
+        index.update_collections(listed);
+
+        index.add_path("/blog/b.md");
+        index.add_path("/papers/c.md");
+        index.rename_path("/blog/a.md", "/blog/z.md");
+        let blog = index.collection_snapshot("blog").unwrap();
+        assert_eq!(*blog, vec!["/blog/z.md", "/blog/b.md"]);
+
+        index.set_collection_roots("blog", Vec::new());
+        assert!(index.collections().is_empty());
+        assert!(index.collection_snapshot("blog").is_none());
+    }
+
+    #[test]
+    fn roots_default_to_the_given_home_dir() {
+        let index = FileIndex::with_home_dir("/home/me".to_string());
+        assert_eq!(index.get_roots(), vec!["/home/me"]);
+        assert!(FileIndex::with_home_dir(String::new())
+            .get_roots()
+            .is_empty());
+
+        index.set_roots(vec!["/papers".to_string()]);
+        assert_eq!(index.get_roots(), vec!["/papers"]);
+    }
+
+    #[test]
+    fn snapshots_are_unaffected_by_later_changes() {
+        let index = FileIndex::new();
+        index.update(vec!["/p/a.md".into()]);
+        let before = index.snapshot();
+
+        index.add_path("/p/b.md");
+        index.update(vec!["/p/c.md".into()]);
+        assert_eq!(*before, vec!["/p/a.md"]);
+        assert_eq!(*index.snapshot(), vec!["/p/c.md"]);
+    }
+
+    #[test]
+    fn content_matches_need_every_word() {
+        let terms = |query: &str| {
+            query
+                .split_whitespace()
+                .map(str::to_lowercase)
+                .collect::<Vec<_>>()
+        };
+        let paper = "# Attention\nSelf-attention scales.\n\nTransformers use attention heads.";
+
+        let found = score_content(paper, &terms("Attention transformers")).unwrap();
+        assert_eq!(found.hits, 3);
+        assert_eq!(found.score, 1 + 1 + 4);
+        assert_eq!(found.snippet, "Transformers use attention heads.");
+
+        assert_eq!(score_content(paper, &terms("attention convolution")), None);
+    }
+
+    #[test]
+    fn words_on_one_line_outrank_scattered_words() {
+        let terms = vec!["graph".to_string(), "neural".to_string()];
+        let together = score_content("graph neural networks", &terms).unwrap();
+        let apart = score_content("graph\nneural", &terms).unwrap();
+        assert!(together.score > apart.score);
+    }
+
+    #[test]
+    fn long_snippets_are_truncated() {
+        let line = format!("  {}  ", "a".repeat(200));
+        let cut = snippet(&line);
+        assert_eq!(cut.chars().count(), SNIPPET_MAX_CHARS);
+        assert!(cut.ends_with('…'));
+    }
+
+    #[test]
+    fn short_queries_only_score_pinned_paths() {
+        let home = "/Users/me";
+        let paths: Vec<String> = (0..100)
+            .map(|i| format!("{}/papers/topic-{}/paper-{}.md", home, i % 3, i))
+            .collect();
+        let pinned = || vec!["/Users/me/papers/topic-1/paper-1.md".to_string(); 2];
+
+        let candidates = search_candidates(Arc::new(paths.clone()), "p", pinned);
+        let scored = score_paths(&candidates, "p", home, 0, MatcherSettings::default());
+        // Only the (deduplicated) favorites and recents are scored
+        assert_eq!(*candidates, vec!["/Users/me/papers/topic-1/paper-1.md"]);
+        assert_eq!(scored.len(), 1);
+
+        let full = search_candidates(Arc::new(paths), "pa", pinned);
+        assert_eq!(full.len(), 100);
+    }
+
+    /// Benchmark; run with `cargo test -- --ignored`
+    #[test]
+    #[ignore]
+    fn short_queries_stay_fast_on_a_large_index() {
+        let home = "/Users/me";
+        let paths: Vec<String> = (0..50_000)
+            .map(|i| format!("{}/papers/topic-{}/paper-{}.md", home, i % 300, i))
+            .collect();
+        let pinned = || vec!["/Users/me/papers/topic-1/paper-1.md".to_string()];
+        let files = Arc::new(paths);
+
+        let started = Instant::now();
+        let candidates = search_candidates(files.clone(), "p", pinned);
+        score_paths(&candidates, "p", home, 0, MatcherSettings::default());
+        let short = started.elapsed();
+
+        let started = Instant::now();
+        let candidates = search_candidates(files, "pa", pinned);
+        score_paths(&candidates, "pa", home, 0, MatcherSettings::default());
+        let full = started.elapsed();
+
+        // A one-character query must not pay for a full scan of the index
+        assert!(
+            short < Duration::from_millis(5),
+            "short query took {:?}",
+            short
+        );
+        assert!(short * 10 < full, "short {:?} vs full {:?}", short, full);
+    }
+
+    #[test]
+    fn edits_replace_the_paths_without_touching_snapshots() {
+        let index = FileIndex::new();
+        index.update(vec!["/p/a.md".into(), "/p/old/b.md".into()]);
+        let before = index.snapshot();
+
+        index.rename_path("/p/old", "/p/new");
+        index.add_path("/p/c.md");
+        index.add_path("/p/c.md");
+        index.remove_path("/p/a.md");
+        assert_eq!(*before, vec!["/p/a.md", "/p/old/b.md"]);
+        assert_eq!(*index.snapshot(), vec!["/p/new/b.md", "/p/c.md"]);
+    }
+
+    #[test]
+    fn freezing_reports_the_previous_state() {
+        let index = FileIndex::new();
+        assert!(!index.is_frozen());
+        assert!(!index.set_frozen(true));
+        assert!(index.is_frozen());
+        assert!(index.set_frozen(false));
+        assert!(!index.is_frozen());
+    }
+
+    #[test]
+    fn focus_refreshes_respect_pause_and_freeze() {
+        let index = FileIndex::new();
+        // An empty index is filled even while frozen, but not while paused
+        index.set_frozen(true);
+        assert!(index.wants_refresh(30));
+        index.set_watching_paused(true);
+        assert!(!index.wants_refresh(30));
+        index.set_watching_paused(false);
+        index.set_frozen(false);
+
+        index.update(vec!["/p/a.md".into()]);
+        assert!(!index.wants_refresh(30));
+        *index.last_refresh.write().unwrap() = Instant::now() - Duration::from_secs(60);
+        assert!(index.wants_refresh(30));
+
+        index.set_frozen(true);
+        assert!(!index.wants_refresh(30));
+        index.set_frozen(false);
+        index.set_watching_paused(true);
+        assert!(!index.wants_refresh(30));
+    }
+
+    #[test]
+    fn content_hashes_follow_index_edits() {
+        let index = FileIndex::new();
+        index.update(vec!["/p/a.md".into(), "/p/b.md".into(), "/p/c.md".into()]);
+        for path in ["/p/a.md", "/p/b.md", "/p/c.md"] {
+            let entry = (SystemTime::UNIX_EPOCH, path.to_string());
+            index
+                .content_hashes
+                .lock()
+                .unwrap()
+                .insert(path.into(), entry);
+        }
+
+        index.rename_path("/p/a.md", "/p/z.md");
+        index.remove_path("/p/b.md");
+        let mut paths: Vec<_> = index
+            .content_hashes
+            .lock()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect();
+        paths.sort();
+        assert_eq!(paths, vec!["/p/c.md", "/p/z.md"]);
+
+        index.retain_content_hashes(["/p/z.md"]);
+        let hashes = index.content_hashes.lock().unwrap();
+        assert_eq!(hashes.keys().collect::<Vec<_>>(), vec!["/p/z.md"]);
+        assert_eq!(hashes["/p/z.md"].1, "/p/a.md");
+    }
+
+    #[test]
+    fn file_stats_follow_index_edits() {
+        let index = FileIndex::new();
+        index.update(vec!["/p/a.md".into(), "/p/b.md".into()]);
+        index.update_file_stats(HashMap::from([
+            ("/p/a.md".to_string(), (10, 1)),
+            ("/p/b.md".to_string(), (20, 2)),
+        ]));
+
+        index.rename_path("/p/a.md", "/p/z.md");
+        index.remove_path("/p/b.md");
+        assert_eq!(index.file_stat("/p/z.md"), Some((10, 1)));
+        assert_eq!(index.file_stat("/p/a.md"), None);
+        assert_eq!(index.file_stat("/p/b.md"), None);
+    }
+
+    #[test]
+    fn refresh_tags_only_rereads_modified_files() {
+        let index = FileIndex::new();
+        let reads = std::cell::RefCell::new(Vec::new());
+        let read = |path: &str| {
+            reads.borrow_mut().push(path.to_string());
+            vec![format!("tag-{}", reads.borrow().len())]
+        };
+
+        let mut stats = HashMap::from([
+            ("/p/a.md".to_string(), (10, 1)),
+            ("/p/b.md".to_string(), (20, 2)),
+        ]);
+        index.refresh_tags(&stats, read);
+        assert_eq!(reads.borrow().len(), 2);
+
+        stats.insert("/p/b.md".to_string(), (25, 3));
+        reads.borrow_mut().clear();
+        let a_tags = index.tags.read().unwrap()["/p/a.md"].clone();
+        index.refresh_tags(&stats, read);
+        assert_eq!(*reads.borrow(), vec!["/p/b.md".to_string()]);
+        assert_eq!(index.tags.read().unwrap()["/p/a.md"], a_tags);
+
+        stats.remove("/p/a.md");
+        index.refresh_tags(&stats, read);
+        assert!(!index.tags.read().unwrap().contains_key("/p/a.md"));
+    }
 }
@@ -0,0 +1,107 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// How much of each file is read when looking for front matter
+const FRONTMATTER_READ_LIMIT: u64 = 8 * 1024;
+
+/// Extract `tags` from YAML front matter.
+///
+/// Accepts the inline array form (`tags: [ml, nlp]`), a comma-separated string
+/// (`tags: ml, nlp`) and the block list form (`tags:` followed by `- ml` lines).
+/// Tags are trimmed of whitespace and quotes; empty entries are dropped.
+pub fn parse_tags(content: &str) -> Vec<String> {
+    let mut lines = content.lines();
+    if lines.next().map(str::trim) != Some("---") {
+        return Vec::new();
+    }
+
+    let mut tags = Vec::new();
+    let mut in_block_list = false;
+
+    for line in lines {
+        let trimmed = line.trim();
+        if trimmed == "---" || trimmed == "..." {
+            break;
+        }
+
+        if in_block_list {
+            if let Some(item) = trimmed.strip_prefix('-') {
+                push_tag(&mut tags, item);
+                continue;
+            }
+            in_block_list = false;
+        }
+
+        let Some(value) = trimmed.strip_prefix("tags:") else {
+            continue;
+        };
+        let value = value.trim();
+        if value.is_empty() {
+            in_block_list = true;
+            continue;
+        }
+
+        let value = value
+            .strip_prefix('[')
+            .and_then(|v| v.strip_suffix(']'))
+            .unwrap_or(value);
+        for item in value.split(',') {
+            push_tag(&mut tags, item);
+        }
+    }
+
+    tags
+}
+
+fn push_tag(tags: &mut Vec<String>, raw: &str) {
+    let tag = raw.trim().trim_matches(['"', '\'']).trim();
+    if !tag.is_empty() && !tags.iter().any(|t| t == tag) {
+        tags.push(tag.to_string());
+    }
+}
+
+/// Read the tags from the front matter at the top of a file
+pub fn read_tags(path: &Path) -> Vec<String> {
+    let Ok(file) = File::open(path) else {
+        return Vec::new();
+    };
+
+    let mut buf = Vec::new();
+    if file
+        .take(FRONTMATTER_READ_LIMIT)
+        .read_to_end(&mut buf)
+        .is_err()
+    {
+        return Vec::new();
+    }
+    parse_tags(&String::from_utf8_lossy(&buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_inline_array() {
+        let content = "---\ntitle: A\ntags: [ml, \"nlp\"]\n---\n# A";
+        assert_eq!(parse_tags(content), vec!["ml", "nlp"]);
+    }
+
+    #[test]
+    fn parses_comma_string() {
+        let content = "---\ntags: ml, nlp ,\n---\n";
+        assert_eq!(parse_tags(content), vec!["ml", "nlp"]);
+    }
+
+    #[test]
+    fn parses_block_list() {
+        let content = "---\ntags:\n  - ml\n  - 'nlp'\ntitle: A\n---\n";
+        assert_eq!(parse_tags(content), vec!["ml", "nlp"]);
+    }
+
+    #[test]
+    fn ignores_files_without_front_matter() {
+        assert!(parse_tags("# Title\ntags: [ml]\n").is_empty());
+    }
+}
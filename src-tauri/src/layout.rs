@@ -0,0 +1,403 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, LogicalPosition, LogicalSize, Manager, Window};
+
+use crate::settings;
+use crate::tabs::{self, TabManager};
+
+/// Default height of the frontend tab bar that content webviews sit below, used
+/// until the frontend reports its measured height with `set_tab_bar_height`
+#[cfg(target_os = "windows")]
+pub const TAB_BAR_HEIGHT: f64 = 40.0;
+#[cfg(not(target_os = "windows"))]
+pub const TAB_BAR_HEIGHT: f64 = 38.0;
+
+/// Largest tab bar height accepted from the frontend
+const MAX_TAB_BAR_HEIGHT: f64 = 200.0;
+
+const HIDE_SINGLE_TAB_BAR_KEY: &str = "hideTabBarWhenSingle";
+
+const TAB_DENSITY_KEY: &str = "tabDensity";
+
+/// Smallest logical width/height given to a webview, so tiny or minimized windows
+/// never produce zero or negative sizes
+const MIN_WEBVIEW_SIZE: f64 = 1.0;
+
+/// Preset tab bar heights, so the frontend renders the bar at the height content
+/// webviews are positioned below
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TabDensity {
+    Compact,
+    Comfortable,
+    Spacious,
+}
+
+impl TabDensity {
+    /// Tab bar height in logical pixels
+    pub fn height(self) -> f64 {
+        match self {
+            TabDensity::Compact => 30.0,
+            TabDensity::Comfortable => 38.0,
+            TabDensity::Spacious => 46.0,
+        }
+    }
+}
+
+/// Payload of `tab-density-changed`
+#[derive(Debug, Clone, Serialize)]
+pub struct TabDensityChange {
+    pub density: TabDensity,
+    pub height: f64,
+}
+
+/// Two tabs shown side by side below the tab bar
+#[derive(Debug, Clone, Serialize)]
+pub struct SplitView {
+    pub left_id: String,
+    pub right_id: String,
+}
+
+impl SplitView {
+    pub fn contains(&self, id: &str) -> bool {
+        self.left_id == id || self.right_id == id
+    }
+}
+
+/// Layout options shared by all code that positions tab and pool webviews
+pub struct Layout {
+    hide_single_tab_bar: AtomicBool,
+    /// Tab bar hidden for focused reading (see `set_tab_bar_visible`); not persisted
+    tab_bar_hidden: AtomicBool,
+    /// Measured tab bar height reported by the frontend
+    tab_bar_height: Mutex<f64>,
+    split: Mutex<Option<SplitView>>,
+}
+
+impl Layout {
+    pub fn new() -> Self {
+        Self {
+            hide_single_tab_bar: AtomicBool::new(false),
+            tab_bar_hidden: AtomicBool::new(false),
+            tab_bar_height: Mutex::new(TAB_BAR_HEIGHT),
+            split: Mutex::new(None),
+        }
+    }
+
+    pub fn split(&self) -> Option<SplitView> {
+        self.split.lock().unwrap().clone()
+    }
+
+    fn set_split(&self, split: Option<SplitView>) -> Option<SplitView> {
+        std::mem::replace(&mut *self.split.lock().unwrap(), split)
+    }
+
+    pub fn hide_single_tab_bar(&self) -> bool {
+        self.hide_single_tab_bar.load(Ordering::Relaxed)
+    }
+
+    pub fn set_hide_single_tab_bar(&self, hide: bool) {
+        self.hide_single_tab_bar.store(hide, Ordering::Relaxed);
+    }
+
+    pub fn tab_bar_hidden(&self) -> bool {
+        self.tab_bar_hidden.load(Ordering::Relaxed)
+    }
+
+    /// Hide or show the tab bar, returning true if that changed it
+    fn set_tab_bar_hidden(&self, hidden: bool) -> bool {
+        self.tab_bar_hidden.swap(hidden, Ordering::Relaxed) != hidden
+    }
+
+    /// Height of the tab bar when it's shown
+    pub fn tab_bar_height(&self) -> f64 {
+        *self.tab_bar_height.lock().unwrap()
+    }
+
+    /// Set the tab bar height, returning true if that changed it
+    fn set_tab_bar_height(&self, height: f64) -> bool {
+        std::mem::replace(&mut *self.tab_bar_height.lock().unwrap(), height) != height
+    }
+}
+
+/// Restore persisted layout settings (called at startup)
+pub fn load_layout_settings(app: &AppHandle) {
+    let layout = app.state::<Layout>();
+    if let Some(hide) = settings::load::<bool>(app, HIDE_SINGLE_TAB_BAR_KEY) {
+        layout.set_hide_single_tab_bar(hide);
+    }
+    if let Some(density) = settings::load::<TabDensity>(app, TAB_DENSITY_KEY) {
+        layout.set_tab_bar_height(density.height());
+    }
+}
+
+/// Effective tab bar height, which is 0 when the bar is hidden (always, or for a single tab)
+pub fn tab_bar_height(app: &AppHandle) -> f64 {
+    let layout = app.state::<Layout>();
+    if layout.tab_bar_hidden()
+        || (layout.hide_single_tab_bar() && app.state::<TabManager>().tab_count() <= 1)
+    {
+        0.0
+    } else {
+        layout.tab_bar_height()
+    }
+}
+
+/// Compute the content area for a window of the given physical size.
+///
+/// Width and height are clamped to MIN_WEBVIEW_SIZE; the resize handler re-lays out
+/// webviews once the window reports a real size.
+fn compute_content_bounds(
+    physical_width: u32,
+    physical_height: u32,
+    scale: f64,
+    top: f64,
+) -> (LogicalPosition<f64>, LogicalSize<f64>) {
+    let scale = if scale > 0.0 { scale } else { 1.0 };
+    let width = physical_width as f64 / scale;
+    let height = (physical_height as f64 / scale) - top;
+
+    (
+        LogicalPosition::new(0.0, top),
+        LogicalSize::new(width.max(MIN_WEBVIEW_SIZE), height.max(MIN_WEBVIEW_SIZE)),
+    )
+}
+
+/// Compute the logical position and size of the content area below the tab bar.
+pub fn content_bounds(
+    app: &AppHandle,
+    window: &Window,
+) -> Result<(LogicalPosition<f64>, LogicalSize<f64>), String> {
+    let size = window.inner_size().map_err(|e| e.to_string())?;
+    let scale = window.scale_factor().unwrap_or(1.0);
+
+    Ok(compute_content_bounds(
+        size.width,
+        size.height,
+        scale,
+        tab_bar_height(app),
+    ))
+}
+
+/// Whether a webview label belongs to a tab or the pool (as opposed to the main UI).
+pub(crate) fn is_content_webview(label: &str) -> bool {
+    label.starts_with("tab-") || label.starts_with("pool-")
+}
+
+/// Reposition and resize every tab and pool webview to the current content area.
+pub fn relayout(app: &AppHandle) {
+    let Some(window) = app.get_window("main") else {
+        return;
+    };
+    let (position, size) = match content_bounds(app, &window) {
+        Ok(bounds) => bounds,
+        Err(e) => {
+            log::error!("Failed to compute content bounds: {}", e);
+            return;
+        }
+    };
+
+    let split = app.state::<Layout>().split();
+    let half = LogicalSize::new(size.width / 2.0, size.height);
+
+    // Tabs showing two papers are laid out like a split view of their two webviews
+    let split_tabs: Vec<(String, String)> = app
+        .state::<TabManager>()
+        .get_state()
+        .tabs
+        .into_iter()
+        .filter_map(|t| t.split.map(|s| (t.id, s.right_label)))
+        .collect();
+
+    for webview in window.webviews() {
+        let label = webview.label();
+        if !is_content_webview(label) {
+            continue;
+        }
+
+        let is_left = split.as_ref().is_some_and(|s| s.left_id == label)
+            || split_tabs.iter().any(|(left, _)| left == label);
+        let is_right = split.as_ref().is_some_and(|s| s.right_id == label)
+            || split_tabs.iter().any(|(_, right)| right == label);
+
+        if is_left {
+            let _ = webview.set_position(position);
+            let _ = webview.set_size(half);
+        } else if is_right {
+            let _ = webview.set_position(LogicalPosition::new(half.width, position.y));
+            let _ = webview.set_size(half);
+        } else {
+            let _ = webview.set_position(position);
+            let _ = webview.set_size(size);
+        }
+    }
+}
+
+/// Leave split view, hiding the half that isn't the active tab.
+///
+/// Returns false if no split was active.
+pub fn end_split(app: &AppHandle) -> bool {
+    let Some(split) = app.state::<Layout>().set_split(None) else {
+        return false;
+    };
+
+    let active = app.state::<TabManager>().get_state().active_tab_id;
+    for id in [&split.left_id, &split.right_id] {
+        if *id != active {
+            tabs::set_tab_visible(app, id, false);
+        }
+    }
+
+    relayout(app);
+    let _ = app.emit("split-view-changed", None::<SplitView>);
+    true
+}
+
+/// Show two tabs side by side, each taking half the width below the tab bar.
+#[tauri::command]
+pub fn split_view(app: AppHandle, left_id: String, right_id: String) -> Result<(), String> {
+    let manager = app.state::<TabManager>();
+    if left_id == right_id {
+        return Err("Cannot split a tab with itself".to_string());
+    }
+    let (Some(left), Some(right)) = (manager.get_tab(&left_id), manager.get_tab(&right_id)) else {
+        return Err("Tab not found".to_string());
+    };
+    if left.split.is_some() || right.split.is_some() {
+        return Err("Tabs already showing two papers can't be split".to_string());
+    }
+
+    tabs::ensure_webview(&app, &left_id)?;
+    tabs::ensure_webview(&app, &right_id)?;
+
+    let split = SplitView { left_id, right_id };
+    let previous = app.state::<Layout>().set_split(Some(split.clone()));
+
+    // Hide whatever was visible before (the active tab or a previous split)
+    let mut visible = vec![manager.get_state().active_tab_id];
+    if let Some(previous) = previous {
+        visible.push(previous.left_id);
+        visible.push(previous.right_id);
+    }
+    for id in visible.iter().filter(|id| !split.contains(id)) {
+        tabs::set_tab_visible(&app, id, false);
+    }
+
+    relayout(&app);
+    for id in [&split.right_id, &split.left_id] {
+        if let Some(webview) = app.get_webview(id) {
+            let _ = webview.show();
+            let _ = webview.set_focus();
+        }
+    }
+
+    manager.set_active(&split.left_id);
+    let _ = app.emit("split-view-changed", Some(split));
+    tabs::emit_tab_state(&app);
+    Ok(())
+}
+
+/// Return to single-pane view, keeping the active tab visible.
+#[tauri::command]
+pub fn unsplit(app: AppHandle) {
+    if end_split(&app) {
+        tabs::emit_tab_state(&app);
+    }
+}
+
+#[tauri::command]
+pub fn get_split_view(app: AppHandle) -> Option<SplitView> {
+    app.state::<Layout>().split()
+}
+
+#[tauri::command]
+pub fn get_hide_single_tab_bar(app: AppHandle) -> bool {
+    app.state::<Layout>().hide_single_tab_bar()
+}
+
+/// Hide the tab bar (giving the webview the full window) while only one tab is open
+#[tauri::command]
+pub fn set_hide_single_tab_bar(app: AppHandle, hide: bool) -> Result<(), String> {
+    app.state::<Layout>().set_hide_single_tab_bar(hide);
+    settings::save(&app, HIDE_SINGLE_TAB_BAR_KEY, &hide)?;
+    relayout(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_tab_bar_visible(app: AppHandle) -> bool {
+    !app.state::<Layout>().tab_bar_hidden()
+}
+
+/// Hide the tab bar for focused reading, giving every tab and pool webview the full
+/// window, or bring it back. Emits `tab-bar-visibility-changed` with the new visibility.
+#[tauri::command]
+pub fn set_tab_bar_visible(app: AppHandle, visible: bool) {
+    if app.state::<Layout>().set_tab_bar_hidden(!visible) {
+        relayout(&app);
+        let _ = app.emit("tab-bar-visibility-changed", visible);
+    }
+}
+
+/// Record the tab bar height measured by the frontend, re-laying out every tab and
+/// pool webview if it changed so none are clipped or left with a gap
+#[tauri::command]
+pub fn set_tab_bar_height(app: AppHandle, height: f64) -> Result<(), String> {
+    if !height.is_finite() || !(0.0..=MAX_TAB_BAR_HEIGHT).contains(&height) {
+        return Err(format!("Invalid tab bar height: {}", height));
+    }
+    if app.state::<Layout>().set_tab_bar_height(height) {
+        relayout(&app);
+    }
+    Ok(())
+}
+
+/// The chosen tab density (persisted), or None while the platform default
+/// `TAB_BAR_HEIGHT` applies
+#[tauri::command]
+pub fn get_tab_density(app: AppHandle) -> Option<TabDensity> {
+    settings::load(&app, TAB_DENSITY_KEY)
+}
+
+/// Switch the tab bar to a preset height, re-laying out every tab and pool webview
+/// below it. Emits `tab-density-changed` so the frontend renders the bar to match.
+#[tauri::command]
+pub fn set_tab_density(app: AppHandle, density: TabDensity) -> Result<(), String> {
+    settings::save(&app, TAB_DENSITY_KEY, &density)?;
+    let height = density.height();
+    if app.state::<Layout>().set_tab_bar_height(height) {
+        relayout(&app);
+    }
+    let _ = app.emit("tab-density-changed", TabDensityChange { density, height });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_bounds_sit_below_tab_bar() {
+        let (position, size) = compute_content_bounds(1600, 1200, 2.0, TAB_BAR_HEIGHT);
+        assert_eq!(position.y, TAB_BAR_HEIGHT);
+        assert_eq!(size.width, 800.0);
+        assert_eq!(size.height, 600.0 - TAB_BAR_HEIGHT);
+    }
+
+    #[test]
+    fn content_bounds_clamp_windows_smaller_than_tab_bar() {
+        let (_, size) = compute_content_bounds(0, 20, 1.0, TAB_BAR_HEIGHT);
+        assert_eq!(size.width, MIN_WEBVIEW_SIZE);
+        assert_eq!(size.height, MIN_WEBVIEW_SIZE);
+    }
+
+    #[test]
+    fn tab_densities_map_to_bar_heights() {
+        let parse = |s: &str| serde_json::from_str::<TabDensity>(s).unwrap();
+        assert_eq!(parse("\"compact\"").height(), 30.0);
+        assert_eq!(parse("\"comfortable\"").height(), 38.0);
+        assert_eq!(parse("\"spacious\"").height(), 46.0);
+        assert!(serde_json::from_str::<TabDensity>("\"cozy\"").is_err());
+    }
+}
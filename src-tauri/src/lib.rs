@@ -1,15 +1,68 @@
-use tauri::menu::{MenuBuilder, MenuItemBuilder, SubmenuBuilder};
-use tauri::{LogicalSize, Manager};
+use tauri::menu::{CheckMenuItemBuilder, MenuBuilder, SubmenuBuilder};
+use tauri::Manager;
 
+mod cli;
+mod commands;
+mod diagnostics;
 mod file_search;
+mod frontmatter;
+mod layout;
+mod library;
+mod open_with;
 mod pool;
+mod reading_list;
+mod session;
+mod settings;
 mod tabs;
+mod window;
 
-use file_search::{refresh_file_index, refresh_if_stale, search_files, FileIndex};
-use pool::WebviewPool;
+use cli::open_from_clipboard;
+use commands::{list_commands, run_command};
+use diagnostics::{
+    clear_cache, get_recent_logs, open_app_data_dir, open_devtools, open_pool_devtools,
+    resource_summary, ActivityLog,
+};
+use file_search::{
+    clear_last_query, count_matches, find_duplicate_papers, format_paths, freeze_index,
+    get_case_mode, get_index_frozen, get_last_query, get_matcher_settings, get_restrict_to_roots,
+    get_search_roots, index_tree, library_overview, list_all_tags, list_collections,
+    list_papers_in_dir, pause_file_watching, perf_stats, refresh_file_index, refresh_if_stale,
+    resume_file_watching, search_by_tag, search_capabilities, search_content, search_files,
+    search_files_detailed, search_in, search_recent, set_case_mode, set_collection_roots,
+    set_matcher_settings, set_restrict_to_roots, set_search_roots, FileIndex, LastQuery,
+};
+use layout::{
+    get_hide_single_tab_bar, get_split_view, get_tab_bar_visible, get_tab_density,
+    set_hide_single_tab_bar, set_tab_bar_height, set_tab_bar_visible, set_tab_density, split_view,
+    unsplit, Layout,
+};
+use library::{
+    clear_search_history, create_paper, get_favorites, get_recents, get_search_history_enabled,
+    get_search_open_log, get_search_open_log_enabled, read_paper, rename_paper, search_suggestions,
+    set_favorite, set_search_history_enabled, set_search_open_log_enabled, trash_paper,
+};
+use open_with::{get_open_with, run_open_with, set_open_with};
+use pool::{
+    ensure_pool_warm, get_pool_navigate_in_place, get_pool_prewarm, get_pool_size,
+    set_pool_navigate_in_place, set_pool_prewarm, set_pool_size, WebviewPool,
+};
+use reading_list::{dequeue, enqueue, list_queue, open_next_from_queue, ReadingListStore};
+use session::{
+    export_session, import_session, list_sessions, load_session, repair_session, save_session,
+    validate_session,
+};
 use tabs::{
-    close_active_tab, close_tab, create_tab, get_tab_state, next_tab, prev_tab, switch_tab,
-    switch_tab_by_index, update_current_tab_title, TabManager, TAB_BAR_HEIGHT,
+    close_active_tab, close_tab, close_tab_range, close_tabs_under, create_locked_tab,
+    create_peek_tab, create_split_tab, create_tab, focus_active_webview, get_adjacent_tabs,
+    get_tab_state, has_unsaved_scratch, is_paper_open, list_recently_closed, move_tab_left,
+    move_tab_right, navigate_ack, next_paper_tab, next_tab, prefetch_paper, prev_paper_tab,
+    prev_tab, promote_peek, reading_progress, reopen_closed_tab_by_id, report_scroll,
+    reset_tab_to_home, set_tab_color, set_tab_theme, set_tab_unsaved, sort_tabs, switch_tab,
+    switch_tab_by_index, switch_to_tab_by_path, tab_ready, update_current_tab_title, TabManager,
+};
+use window::{
+    force_quit, get_title_follows_tab, open_new_window, set_always_on_top, set_title_follows_tab,
+    QuitGuard, WindowPosition,
 };
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -19,9 +72,15 @@ pub fn run() {
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
+        .manage(ActivityLog::new())
         .manage(TabManager::new())
         .manage(WebviewPool::new())
+        .manage(Layout::new())
         .manage(FileIndex::new())
+        .manage(LastQuery::new())
+        .manage(ReadingListStore::new())
+        .manage(WindowPosition::new())
+        .manage(QuitGuard::new())
         .setup(|app| {
             if cfg!(debug_assertions) {
                 app.handle().plugin(
@@ -34,32 +93,40 @@ pub fn run() {
             // Build custom application menu
             let handle = app.handle();
 
-            // Create menu items
-            let new_tab = MenuItemBuilder::with_id("new_tab", "New Tab")
-                .accelerator("CmdOrCtrl+T")
-                .build(handle)?;
-
-            let close_tab_item = MenuItemBuilder::with_id("close_tab", "Close Tab")
-                .accelerator("CmdOrCtrl+W")
-                .build(handle)?;
-
-            let next_tab_item = MenuItemBuilder::with_id("next_tab", "Next Tab")
-                .accelerator("Ctrl+Tab")
-                .build(handle)?;
-
-            let prev_tab_item = MenuItemBuilder::with_id("prev_tab", "Previous Tab")
-                .accelerator("Ctrl+Shift+Tab")
-                .build(handle)?;
+            // Create menu items (titles and accelerators come from the command registry)
+            let new_window = commands::menu_item(handle, "new_window")?;
+            let new_tab = commands::menu_item(handle, "new_tab")?;
+            let close_tab_item = commands::menu_item(handle, "close_tab")?;
+            let next_tab_item = commands::menu_item(handle, "next_tab")?;
+            let prev_tab_item = commands::menu_item(handle, "prev_tab")?;
+            let move_tab_left_item = commands::menu_item(handle, "move_tab_left")?;
+            let move_tab_right_item = commands::menu_item(handle, "move_tab_right")?;
 
             // Build File submenu
             let file_menu = SubmenuBuilder::new(handle, "File")
+                .item(&new_window)
                 .item(&new_tab)
                 .item(&close_tab_item)
                 .separator()
                 .item(&next_tab_item)
                 .item(&prev_tab_item)
+                .item(&move_tab_left_item)
+                .item(&move_tab_right_item)
+                .build()?;
+
+            let always_on_top_item =
+                CheckMenuItemBuilder::with_id(window::ALWAYS_ON_TOP_MENU_ID, "Always on Top")
+                    .checked(window::always_on_top(handle))
+                    .build(handle)?;
+
+            // Build View submenu
+            let view_menu = SubmenuBuilder::with_id(handle, window::VIEW_MENU_ID, "View")
+                .item(&always_on_top_item)
                 .build()?;
 
+            let open_app_data_item = commands::menu_item(handle, "open_app_data_dir")?;
+            let clear_cache_item = commands::menu_item(handle, "clear_cache")?;
+
             // Build the full menu with standard Edit menu for copy/paste
             let edit_menu = SubmenuBuilder::new(handle, "Edit")
                 .undo()
@@ -71,46 +138,61 @@ pub fn run() {
                 .select_all()
                 .build()?;
 
+            // Build Help submenu with maintenance actions
+            let help_menu = SubmenuBuilder::new(handle, "Help")
+                .item(&open_app_data_item)
+                .item(&clear_cache_item)
+                .build()?;
+
             let menu = MenuBuilder::new(handle)
                 .item(&file_menu)
                 .item(&edit_menu)
+                .item(&view_menu)
+                .item(&help_menu)
                 .build()?;
 
             app.set_menu(menu)?;
 
-            // Handle menu events
+            // Handle menu events through the same registry the command palette uses
             let app_handle_for_menu = handle.clone();
             app.on_menu_event(move |_app, event| {
-                match event.id().as_ref() {
-                    "new_tab" => {
-                        let _ = tabs::create_tab_internal_from_menu(&app_handle_for_menu);
-                    }
-                    "close_tab" => {
-                        let _ = tabs::close_tab_or_window(&app_handle_for_menu);
-                    }
-                    "next_tab" => {
-                        let _ = next_tab(app_handle_for_menu.clone());
-                    }
-                    "prev_tab" => {
-                        let _ = prev_tab(app_handle_for_menu.clone());
-                    }
-                    _ => {}
+                let Some(command) = commands::find(event.id().as_ref()) else {
+                    return;
+                };
+                if let Err(e) = command.run(&app_handle_for_menu) {
+                    log::error!("Menu command {} failed: {}", command.id, e);
                 }
             });
 
             // Create initial home tab
             let handle = app.handle().clone();
+            layout::load_layout_settings(&handle);
+            window::load_window_position(&handle);
+            window::load_always_on_top(&handle);
+            let saved_session = session::load_autosave(&handle);
             tabs::create_initial_tab(&handle)?;
 
-            // Initialize the webview pool
+            // Warm the webview pool in the background
             pool::initialize_pool(&handle);
 
+            // Reopen the tabs from the previous launch
+            if let Some(saved) = saved_session {
+                session::restore_session(&handle, saved);
+            }
+
+            // Open papers passed on the command line
+            cli::open_cli_args(&handle);
+
             // Initialize file index (background refresh)
+            file_search::load_search_settings(&handle);
             refresh_if_stale(&handle, 0);
+            file_search::load_last_query(&handle);
+            reading_list::load_reading_list(&handle);
 
             // Set up window resize listener to resize all child webviews
             let app_handle = app.handle().clone();
             let app_handle_for_focus = app.handle().clone();
+            let app_handle_for_move = app.handle().clone();
             if let Some(window) = app.get_window("main") {
                 // Refresh file index on window focus (if stale > 30s)
                 window.on_window_event(move |event| {
@@ -119,34 +201,25 @@ pub fn run() {
                     }
                 });
 
+                // Ask before closing over unsaved scratch tabs
+                let app_handle_for_close = app.handle().clone();
+                window.on_window_event(move |event| {
+                    if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                        window::on_close_requested(&app_handle_for_close, api);
+                    }
+                });
+
+                // Remember where the window was left (persisted once a drag settles)
+                window.on_window_event(move |event| {
+                    if let tauri::WindowEvent::Moved(position) = event {
+                        window::on_moved(&app_handle_for_move, *position);
+                    }
+                });
+
                 window.on_window_event(move |event| {
-                    if let tauri::WindowEvent::Resized(size) = event {
-                        let scale = app_handle
-                            .get_window("main")
-                            .and_then(|w| w.scale_factor().ok())
-                            .unwrap_or(1.0);
-
-                        let width = size.width as f64 / scale;
-                        let height = (size.height as f64 / scale) - TAB_BAR_HEIGHT;
-                        let new_size = LogicalSize::new(width, height);
-
-                        // Resize all tab webviews
-                        let manager = app_handle.state::<TabManager>();
-                        let state = manager.get_state();
-                        for tab in &state.tabs {
-                            if let Some(webview) = app_handle.get_webview(&tab.id) {
-                                let _ = webview.set_size(new_size);
-                            }
-                        }
-
-                        // Resize pool webviews too
-                        if let Some(window) = app_handle.get_window("main") {
-                            for webview in window.webviews() {
-                                if webview.label().starts_with("pool-") {
-                                    let _ = webview.set_size(new_size);
-                                }
-                            }
-                        }
+                    if let tauri::WindowEvent::Resized(_) = event {
+                        // Resize all tab and pool webviews
+                        layout::relayout(&app_handle);
                     }
                 });
             }
@@ -155,17 +228,143 @@ pub fn run() {
         })
         .invoke_handler(tauri::generate_handler![
             create_tab,
+            create_split_tab,
+            create_peek_tab,
+            create_locked_tab,
+            promote_peek,
             close_tab,
             close_active_tab,
+            close_tab_range,
+            close_tabs_under,
             switch_tab,
+            focus_active_webview,
             next_tab,
             prev_tab,
+            next_paper_tab,
+            prev_paper_tab,
             switch_tab_by_index,
+            get_adjacent_tabs,
+            move_tab_left,
+            move_tab_right,
+            sort_tabs,
             get_tab_state,
+            is_paper_open,
+            switch_to_tab_by_path,
             update_current_tab_title,
+            tab_ready,
+            navigate_ack,
+            report_scroll,
+            reading_progress,
+            set_tab_unsaved,
+            has_unsaved_scratch,
+            prefetch_paper,
+            reset_tab_to_home,
+            set_tab_theme,
+            set_tab_color,
+            list_recently_closed,
+            reopen_closed_tab_by_id,
+            repair_session,
+            validate_session,
+            save_session,
+            list_sessions,
+            load_session,
+            export_session,
+            import_session,
+            get_hide_single_tab_bar,
+            set_hide_single_tab_bar,
+            get_tab_bar_visible,
+            set_tab_bar_visible,
+            set_tab_bar_height,
+            get_tab_density,
+            set_tab_density,
+            split_view,
+            unsplit,
+            get_split_view,
+            get_pool_size,
+            ensure_pool_warm,
+            set_pool_size,
+            get_pool_navigate_in_place,
+            set_pool_navigate_in_place,
+            get_pool_prewarm,
+            set_pool_prewarm,
+            open_new_window,
+            open_from_clipboard,
+            set_always_on_top,
+            get_title_follows_tab,
+            set_title_follows_tab,
+            force_quit,
             search_files,
+            search_files_detailed,
+            search_in,
+            search_content,
+            search_recent,
+            format_paths,
+            get_favorites,
+            set_favorite,
+            get_recents,
+            get_search_open_log,
+            get_search_open_log_enabled,
+            set_search_open_log_enabled,
+            get_search_history_enabled,
+            set_search_history_enabled,
+            search_suggestions,
+            clear_search_history,
+            create_paper,
+            rename_paper,
+            trash_paper,
+            read_paper,
+            get_open_with,
+            set_open_with,
+            run_open_with,
+            enqueue,
+            dequeue,
+            list_queue,
+            open_next_from_queue,
+            count_matches,
+            perf_stats,
             refresh_file_index,
+            pause_file_watching,
+            resume_file_watching,
+            freeze_index,
+            get_index_frozen,
+            search_capabilities,
+            get_search_roots,
+            set_search_roots,
+            list_collections,
+            set_collection_roots,
+            get_restrict_to_roots,
+            set_restrict_to_roots,
+            get_matcher_settings,
+            set_matcher_settings,
+            get_case_mode,
+            set_case_mode,
+            list_papers_in_dir,
+            index_tree,
+            library_overview,
+            find_duplicate_papers,
+            search_by_tag,
+            list_all_tags,
+            get_last_query,
+            clear_last_query,
+            open_app_data_dir,
+            clear_cache,
+            open_devtools,
+            open_pool_devtools,
+            resource_summary,
+            get_recent_logs,
+            list_commands,
+            run_command,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| match event {
+            // Tabs still open at quit are never closed, so their stdin files go here
+            tauri::RunEvent::Exit => tabs::remove_ephemeral_files(app),
+            // Quitting asks first over unsaved scratch tabs, like closing the window
+            tauri::RunEvent::ExitRequested { api, .. } => window::on_exit_requested(app, &api),
+            // Markdown files opened with the app from Finder
+            #[cfg(target_os = "macos")]
+            tauri::RunEvent::Opened { urls } => cli::open_documents(app, &urls),
+            _ => {}
+        });
 }
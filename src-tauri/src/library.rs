@@ -0,0 +1,807 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::file_search::{self, FileIndex};
+use crate::reading_list;
+use crate::settings;
+use crate::tabs::{self, TabManager};
+
+const FAVORITES_KEY: &str = "favoritePapers";
+const RECENTS_KEY: &str = "recentPapers";
+const RECENT_OPENED_KEY: &str = "recentPapersOpenedAt";
+const SEARCH_OPEN_LOG_KEY: &str = "searchOpenLog";
+const SEARCH_OPEN_LOG_ENABLED_KEY: &str = "searchOpenLogEnabled";
+const SEARCH_HISTORY_KEY: &str = "searchHistory";
+const SEARCH_HISTORY_ENABLED_KEY: &str = "searchHistoryEnabled";
+
+/// How many recently opened papers are remembered
+const MAX_RECENTS: usize = 50;
+
+/// How many search-then-open entries the local log keeps
+const MAX_SEARCH_OPEN_LOG: usize = 500;
+
+/// How many queries the search history keeps
+const MAX_SEARCH_HISTORY: usize = 100;
+
+/// Most suggestions `search_suggestions` returns
+const MAX_SEARCH_SUGGESTIONS: usize = 8;
+
+/// Largest paper `read_paper` returns
+const MAX_PAPER_BYTES: u64 = 8 * 1024 * 1024;
+
+/// A paper opened from a search result, kept only in the local store
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchOpen {
+    pub query: String,
+    pub path: String,
+    /// When the paper was opened, in milliseconds since the Unix epoch
+    pub opened_ms: u64,
+}
+
+/// A paper written by `create_paper` and the tab it was opened in
+#[derive(Debug, Serialize)]
+pub struct CreatedPaper {
+    pub path: String,
+    pub tab_id: String,
+}
+
+/// A paper's raw markdown, returned by `read_paper`
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct PaperText {
+    pub content: String,
+    /// The file wasn't valid UTF-8, so invalid bytes were replaced with U+FFFD
+    pub lossy: bool,
+}
+
+impl PaperText {
+    /// Read at most `max` bytes as text, failing if there are more
+    fn read(mut reader: impl Read, max: u64) -> Result<Self, String> {
+        let mut bytes = Vec::new();
+        reader
+            .by_ref()
+            .take(max + 1)
+            .read_to_end(&mut bytes)
+            .map_err(|e| e.to_string())?;
+        if bytes.len() as u64 > max {
+            return Err(format!("Paper is larger than {} bytes", max));
+        }
+        Ok(match String::from_utf8(bytes) {
+            Ok(content) => Self {
+                content,
+                lossy: false,
+            },
+            Err(e) => Self {
+                content: String::from_utf8_lossy(e.as_bytes()).into_owned(),
+                lossy: true,
+            },
+        })
+    }
+}
+
+/// Papers the user has marked as favorites, in the order they were added
+pub fn favorites(app: &AppHandle) -> Vec<String> {
+    settings::load(app, FAVORITES_KEY).unwrap_or_default()
+}
+
+/// Recently opened papers, most recent first
+pub fn recents(app: &AppHandle) -> Vec<String> {
+    settings::load(app, RECENTS_KEY).unwrap_or_default()
+}
+
+/// When each recent paper was last opened, in milliseconds since the Unix epoch
+pub fn recent_open_times(app: &AppHandle) -> HashMap<String, u64> {
+    settings::load(app, RECENT_OPENED_KEY).unwrap_or_default()
+}
+
+/// Move a path to the front of the list, dropping the oldest beyond `max`
+fn push_recent(recents: &mut Vec<String>, path: &str, max: usize) {
+    recents.retain(|p| p != path);
+    recents.insert(0, path.to_string());
+    recents.truncate(max);
+}
+
+/// Remember that a paper was opened
+pub fn record_recent(app: &AppHandle, path: &str) {
+    let mut list = recents(app);
+    push_recent(&mut list, path, MAX_RECENTS);
+    if let Err(e) = settings::save(app, RECENTS_KEY, &list) {
+        log::warn!("Failed to persist recent papers: {}", e);
+    }
+
+    let opened_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64);
+    let mut times = recent_open_times(app);
+    times.insert(path.to_string(), opened_ms);
+    times.retain(|p, _| list.contains(p));
+    if let Err(e) = settings::save(app, RECENT_OPENED_KEY, &times) {
+        log::warn!("Failed to persist recent paper open times: {}", e);
+    }
+}
+
+/// Where a path ends up after `old` is moved to `new`, or None if it isn't affected.
+///
+/// Covers the moved path itself and, when a folder is moved, everything inside it.
+pub fn moved_path(path: &str, old: &str, new: &str) -> Option<String> {
+    let rest = Path::new(path).strip_prefix(old).ok()?;
+    if rest.as_os_str().is_empty() {
+        Some(new.to_string())
+    } else {
+        Some(Path::new(new).join(rest).to_string_lossy().to_string())
+    }
+}
+
+/// Rewrite moved paths in a list, returning true if any changed
+fn rename_in(list: &mut [String], old: &str, new: &str) -> bool {
+    let mut changed = false;
+    for path in list.iter_mut() {
+        if let Some(moved) = moved_path(path, old, new) {
+            *path = moved;
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Rewrite moved paths among a map's keys, returning true if any changed
+fn rename_keys(map: &mut HashMap<String, u64>, old: &str, new: &str) -> bool {
+    let moved: Vec<(String, String)> = map
+        .keys()
+        .filter_map(|path| Some((path.clone(), moved_path(path, old, new)?)))
+        .collect();
+    for (from, to) in &moved {
+        if let Some(value) = map.remove(from) {
+            map.insert(to.clone(), value);
+        }
+    }
+    !moved.is_empty()
+}
+
+/// Apply an edit to a stored value, saving it if the edit reports a change
+fn edit_store<T: Default + Serialize + DeserializeOwned>(
+    app: &AppHandle,
+    key: &str,
+    edit: impl FnOnce(&mut T) -> bool,
+) -> Result<(), String> {
+    let mut list: T = settings::load(app, key).unwrap_or_default();
+    if edit(&mut list) {
+        settings::save(app, key, &list)?;
+    }
+    Ok(())
+}
+
+/// Reject paths outside the search roots and collection roots; moving or deleting
+/// papers is always confined to them
+fn check_within_roots(index: &FileIndex, paths: &[&Path]) -> Result<(), String> {
+    let roots = index.all_roots();
+    match paths
+        .iter()
+        .find(|p| !file_search::is_within_roots(p, &roots))
+    {
+        Some(path) => Err(format!("{} is outside the search roots", path.display())),
+        None => Ok(()),
+    }
+}
+
+fn search_open_log_enabled(app: &AppHandle) -> bool {
+    settings::load(app, SEARCH_OPEN_LOG_ENABLED_KEY).unwrap_or(false)
+}
+
+/// Add an entry to the front of the log, dropping the oldest beyond `max`
+fn push_search_open(log: &mut Vec<SearchOpen>, entry: SearchOpen, max: usize) {
+    log.insert(0, entry);
+    log.truncate(max);
+}
+
+/// Remember which paper a search led to, if the search-open log is enabled
+pub fn record_search_open(app: &AppHandle, query: &str, path: &str) {
+    let query = query.trim();
+    if query.is_empty() || !search_open_log_enabled(app) {
+        return;
+    }
+
+    let opened_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64);
+    let mut log: Vec<SearchOpen> = settings::load(app, SEARCH_OPEN_LOG_KEY).unwrap_or_default();
+    let entry = SearchOpen {
+        query: query.to_string(),
+        path: path.to_string(),
+        opened_ms,
+    };
+    push_search_open(&mut log, entry, MAX_SEARCH_OPEN_LOG);
+    if let Err(e) = settings::save(app, SEARCH_OPEN_LOG_KEY, &log) {
+        log::warn!("Failed to persist search-open log: {}", e);
+    }
+}
+
+/// Whether queries are kept for `search_suggestions` (on unless turned off)
+fn search_history_enabled(app: &AppHandle) -> bool {
+    settings::load(app, SEARCH_HISTORY_ENABLED_KEY).unwrap_or(true)
+}
+
+/// Remember a query that led to opening a paper for `search_suggestions`, unless the
+/// search history is turned off
+pub fn record_search_query(app: &AppHandle, query: &str) {
+    let query = query.trim();
+    if query.is_empty() || !search_history_enabled(app) {
+        return;
+    }
+    let mut history: Vec<String> = settings::load(app, SEARCH_HISTORY_KEY).unwrap_or_default();
+    push_recent(&mut history, query, MAX_SEARCH_HISTORY);
+    if let Err(e) = settings::save(app, SEARCH_HISTORY_KEY, &history) {
+        log::warn!("Failed to persist search history: {}", e);
+    }
+}
+
+/// Whether the query's characters appear in order in the candidate (case-insensitive)
+fn is_subsequence(query: &str, candidate: &str) -> bool {
+    let mut chars = candidate.chars().flat_map(char::to_lowercase);
+    query
+        .chars()
+        .flat_map(char::to_lowercase)
+        .all(|q| chars.any(|c| c == q))
+}
+
+/// Queries from the history (most recent first) that start with or fuzzy-match the
+/// prefix, skipping repeats that differ only in case and the prefix itself
+fn suggest(history: &[String], prefix: &str, max: usize) -> Vec<String> {
+    let prefix = prefix.trim();
+    let mut seen = HashSet::new();
+    history
+        .iter()
+        .filter(|query| !query.eq_ignore_ascii_case(prefix))
+        .filter(|query| is_subsequence(prefix, query))
+        .filter(|query| seen.insert(query.to_lowercase()))
+        .take(max)
+        .cloned()
+        .collect()
+}
+
+/// Earlier queries for an autocomplete dropdown, most recent first
+#[tauri::command]
+pub fn search_suggestions(app: AppHandle, prefix: String) -> Vec<String> {
+    let history: Vec<String> = settings::load(&app, SEARCH_HISTORY_KEY).unwrap_or_default();
+    suggest(&history, &prefix, MAX_SEARCH_SUGGESTIONS)
+}
+
+#[tauri::command]
+pub fn clear_search_history(app: AppHandle) -> Result<(), String> {
+    settings::remove(&app, SEARCH_HISTORY_KEY)
+}
+
+#[tauri::command]
+pub fn get_search_history_enabled(app: AppHandle) -> bool {
+    search_history_enabled(&app)
+}
+
+/// Turn the search history behind `search_suggestions` on or off.
+///
+/// Turning it off also clears the queries kept so far.
+#[tauri::command]
+pub fn set_search_history_enabled(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::save(&app, SEARCH_HISTORY_ENABLED_KEY, &enabled)?;
+    if !enabled {
+        settings::remove(&app, SEARCH_HISTORY_KEY)?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_favorites(app: AppHandle) -> Vec<String> {
+    favorites(&app)
+}
+
+/// Add or remove a paper from the favorites
+#[tauri::command]
+pub fn set_favorite(app: AppHandle, path: String, favorite: bool) -> Result<(), String> {
+    let mut list = favorites(&app);
+    let present = list.contains(&path);
+    if favorite && !present {
+        list.push(path);
+    } else if !favorite && present {
+        list.retain(|p| *p != path);
+    } else {
+        return Ok(());
+    }
+    settings::save(&app, FAVORITES_KEY, &list)
+}
+
+#[tauri::command]
+pub fn get_recents(app: AppHandle) -> Vec<String> {
+    recents(&app)
+}
+
+#[tauri::command]
+pub fn get_search_open_log_enabled(app: AppHandle) -> bool {
+    search_open_log_enabled(&app)
+}
+
+/// Turn the local log of papers opened from searches on or off.
+///
+/// Turning it off also clears the entries logged so far.
+#[tauri::command]
+pub fn set_search_open_log_enabled(app: AppHandle, enabled: bool) -> Result<(), String> {
+    settings::save(&app, SEARCH_OPEN_LOG_ENABLED_KEY, &enabled)?;
+    if !enabled {
+        settings::remove(&app, SEARCH_OPEN_LOG_KEY)?;
+    }
+    Ok(())
+}
+
+/// Papers opened from searches, most recent first (up to `limit`, default all)
+#[tauri::command]
+pub fn get_search_open_log(app: AppHandle, limit: Option<usize>) -> Vec<SearchOpen> {
+    let mut log: Vec<SearchOpen> = settings::load(&app, SEARCH_OPEN_LOG_KEY).unwrap_or_default();
+    log.truncate(limit.unwrap_or(usize::MAX));
+    log
+}
+
+/// Move or rename a paper on disk and point everything that referenced it at the new path:
+/// open and recently closed tabs, the index, favorites, recents and the reading list.
+///
+/// Both paths must be inside the search roots and the target must not exist yet.
+#[tauri::command]
+pub fn rename_paper(app: AppHandle, old_path: String, new_path: String) -> Result<(), String> {
+    let (old, new) = (Path::new(&old_path), Path::new(&new_path));
+    if !old.exists() {
+        return Err(format!("Paper not found: {}", old_path));
+    }
+
+    // The target doesn't exist yet, so check the folder it's moved into
+    let index = app.state::<FileIndex>();
+    check_within_roots(&index, &[old, new.parent().unwrap_or(new)])?;
+
+    rename_no_replace(old, new).map_err(|e| match e.kind() {
+        io::ErrorKind::AlreadyExists => format!("{} already exists", new_path),
+        _ => format!("Failed to move {}: {}", old_path, e),
+    })?;
+
+    index.rename_path(&old_path, &new_path);
+    let rerouted = app.state::<TabManager>().rename_path(&old_path, &new_path);
+    if !rerouted.is_empty() {
+        tabs::reroute_webviews(&app, &rerouted);
+        tabs::emit_tab_state(&app);
+    }
+    for key in [FAVORITES_KEY, RECENTS_KEY] {
+        edit_store(&app, key, |list: &mut Vec<String>| {
+            rename_in(list, &old_path, &new_path)
+        })?;
+    }
+    edit_store(&app, RECENT_OPENED_KEY, |times| {
+        rename_keys(times, &old_path, &new_path)
+    })?;
+    reading_list::rename_path(&app, &old_path, &new_path)?;
+
+    let _ = app.emit("paper-renamed", (&old_path, &new_path));
+    Ok(())
+}
+
+/// Rename `old` to `new`, failing with `AlreadyExists` instead of replacing a target
+/// that appears between the caller's checks and the move.
+///
+/// Uses the platform's atomic no-replace rename (`renameat2` on Linux, `renamex_np`
+/// on macOS). Filesystems without it, and other platforms, fall back to checking
+/// for the target right before a plain rename.
+fn rename_no_replace(old: &Path, new: &Path) -> io::Result<()> {
+    match rename_exclusive(old, new) {
+        Err(e) if is_unsupported(&e) => {}
+        result => return result,
+    }
+    if new.symlink_metadata().is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "target already exists",
+        ));
+    }
+    fs::rename(old, new)
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn rename_exclusive(old: &Path, new: &Path) -> io::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let old = CString::new(old.as_os_str().as_bytes())?;
+    let new = CString::new(new.as_os_str().as_bytes())?;
+    // SAFETY: both pointers are NUL-terminated strings that outlive the call
+    #[cfg(target_os = "linux")]
+    let rc = unsafe {
+        libc::renameat2(
+            libc::AT_FDCWD,
+            old.as_ptr(),
+            libc::AT_FDCWD,
+            new.as_ptr(),
+            libc::RENAME_NOREPLACE,
+        )
+    };
+    // SAFETY: as above
+    #[cfg(target_os = "macos")]
+    let rc = unsafe { libc::renamex_np(old.as_ptr(), new.as_ptr(), libc::RENAME_EXCL) };
+    if rc == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn rename_exclusive(_old: &Path, _new: &Path) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// Whether the no-replace rename isn't available here (old kernel, FUSE, SMB, ...)
+fn is_unsupported(err: &io::Error) -> bool {
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    if let Some(code) = err.raw_os_error() {
+        return [libc::EINVAL, libc::ENOSYS, libc::ENOTSUP, libc::EOPNOTSUPP].contains(&code);
+    }
+    err.kind() == io::ErrorKind::Unsupported
+}
+
+/// Turn a user-entered name into a markdown file name.
+///
+/// Path separators and characters invalid on common filesystems become `-`, leading
+/// dots are dropped (no hidden files) and `.md` is appended unless already present.
+fn sanitize_file_name(name: &str) -> Option<String> {
+    let cleaned: String = name
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
+            c if c.is_control() => '-',
+            c => c,
+        })
+        .collect();
+    let cleaned = cleaned.trim_start_matches('.').trim();
+    if cleaned.is_empty() {
+        return None;
+    }
+
+    if cleaned.to_lowercase().ends_with(".md") {
+        Some(cleaned.to_string())
+    } else {
+        Some(format!("{}.md", cleaned))
+    }
+}
+
+/// Starting content for a new paper: optional front matter tags, then an H1 title.
+///
+/// Tags are written as a block list of double-quoted scalars, so commas, colons,
+/// brackets or newlines in a tag can't break the YAML.
+fn new_paper_content(title: &str, tags: &[String]) -> String {
+    let mut content = String::new();
+    if !tags.is_empty() {
+        content.push_str("---\ntags:\n");
+        for tag in tags {
+            // JSON string escaping is valid YAML double-quoted syntax
+            let quoted = serde_json::to_string(tag).unwrap_or_default();
+            content.push_str(&format!("  - {}\n", quoted));
+        }
+        content.push_str("---\n\n");
+    }
+    content.push_str(&format!("# {}\n", title));
+    content
+}
+
+/// Write a new markdown paper into a folder inside the search roots and open it in a tab.
+///
+/// The name is sanitized into a file name; an existing file is never overwritten.
+#[tauri::command]
+pub fn create_paper(
+    app: AppHandle,
+    dir: String,
+    name: String,
+    tags: Option<Vec<String>>,
+) -> Result<CreatedPaper, String> {
+    let dir_path = Path::new(&dir);
+    if !dir_path.is_dir() {
+        return Err(format!("Not a directory: {}", dir));
+    }
+    let index = app.state::<FileIndex>();
+    check_within_roots(&index, &[dir_path])?;
+
+    let file_name = sanitize_file_name(&name).ok_or("Paper name cannot be empty")?;
+    let path = dir_path.join(&file_name);
+    let title = file_name[..file_name.len() - ".md".len()].to_string();
+
+    // create_new fails if the file exists, so a racing write can't be clobbered either
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let content = new_paper_content(&title, &tags.unwrap_or_default());
+    if let Err(e) = file.write_all(content.as_bytes()) {
+        // Don't leave a half-written paper behind (it was created just above)
+        drop(file);
+        let _ = fs::remove_file(&path);
+        return Err(format!("Failed to write {}: {}", path.display(), e));
+    }
+
+    let path = path.to_string_lossy().to_string();
+    index.add_path(&path);
+    let tab_id = tabs::create_tab_with_pool(&app, tabs::tab_for_path(&path))?;
+    Ok(CreatedPaper { path, tab_id })
+}
+
+/// Run a trash command, surfacing its stderr if it fails
+fn run_trash_command(command: &mut Command) -> Result<(), String> {
+    let output = command
+        .output()
+        .map_err(|e| format!("Failed to move to trash: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Failed to move to trash: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// Move a file or folder to the Trash via Finder, which keeps "Put Back" working
+#[cfg(target_os = "macos")]
+fn move_to_trash(path: &Path) -> Result<(), String> {
+    // Passing the path as an argument avoids quoting it inside the script
+    run_trash_command(
+        Command::new("osascript")
+            .args(["-e", "on run argv"])
+            .args([
+                "-e",
+                "tell application \"Finder\" to delete POSIX file (item 1 of argv)",
+            ])
+            .args(["-e", "end run"])
+            .arg(path),
+    )
+}
+
+/// Move a file or folder to the freedesktop trash via GIO
+#[cfg(target_os = "linux")]
+fn move_to_trash(path: &Path) -> Result<(), String> {
+    run_trash_command(Command::new("gio").arg("trash").arg(path))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+fn move_to_trash(_path: &Path) -> Result<(), String> {
+    Err("Moving to the trash isn't supported on this platform".to_string())
+}
+
+/// A paper's raw markdown, e.g. for copying it or building an outline.
+///
+/// The path must be a paper file (see `tabs::is_supported_file`) inside the search
+/// roots or a collection's, and the file at most `MAX_PAPER_BYTES`; invalid UTF-8 is
+/// replaced and reported with `lossy`.
+#[tauri::command]
+pub fn read_paper(app: AppHandle, path: String) -> Result<PaperText, String> {
+    let file = fs::canonicalize(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    // Checked on the resolved file, so a `.md` symlink can't expose anything else
+    if !tabs::is_supported_file(&file) {
+        return Err(format!("{} isn't a paper", path));
+    }
+    if !file_search::is_within_roots(&file, &app.state::<FileIndex>().all_roots()) {
+        return Err(format!("{} is outside the search roots", path));
+    }
+    let reader = fs::File::open(&file).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    PaperText::read(reader, MAX_PAPER_BYTES).map_err(|e| format!("{}: {}", path, e))
+}
+
+/// Move a paper to the OS trash and drop it from the index, open tabs, favorites,
+/// recents and the reading list.
+///
+/// The path must be inside the search roots. Tabs showing the paper go back to the library.
+#[tauri::command]
+pub fn trash_paper(app: AppHandle, path: String) -> Result<(), String> {
+    let target = Path::new(&path);
+    if !target.exists() {
+        return Err(format!("Paper not found: {}", path));
+    }
+    let index = app.state::<FileIndex>();
+    check_within_roots(&index, &[target])?;
+
+    move_to_trash(target)?;
+
+    index.remove_path(&path);
+    for id in app.state::<TabManager>().tabs_showing(&path) {
+        tabs::reset_tab_to_home(app.clone(), id)?;
+    }
+    for key in [FAVORITES_KEY, RECENTS_KEY] {
+        edit_store(&app, key, |list: &mut Vec<String>| {
+            let len = list.len();
+            list.retain(|p| !Path::new(p).starts_with(target));
+            list.len() != len
+        })?;
+    }
+    edit_store(
+        &app,
+        RECENT_OPENED_KEY,
+        |times: &mut HashMap<String, u64>| {
+            let len = times.len();
+            times.retain(|p, _| !Path::new(p).starts_with(target));
+            times.len() != len
+        },
+    )?;
+    reading_list::prune_missing(&app)?;
+
+    let _ = app.emit("paper-trashed", &path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_recent_moves_existing_path_to_front() {
+        let mut recents = vec!["/a.md".to_string(), "/b.md".to_string()];
+        push_recent(&mut recents, "/b.md", 10);
+        assert_eq!(recents, vec!["/b.md", "/a.md"]);
+    }
+
+    #[test]
+    fn rename_keys_follows_moved_folders() {
+        let mut times = HashMap::from([
+            ("/p/old/a.md".to_string(), 1),
+            ("/p/other.md".to_string(), 2),
+        ]);
+        assert!(rename_keys(&mut times, "/p/old", "/p/new"));
+        assert_eq!(times.get("/p/new/a.md"), Some(&1));
+        assert_eq!(times.get("/p/other.md"), Some(&2));
+        assert!(!rename_keys(&mut times, "/p/old", "/p/new"));
+    }
+
+    #[test]
+    fn push_recent_drops_oldest_beyond_max() {
+        let mut recents = vec!["/a.md".to_string(), "/b.md".to_string()];
+        push_recent(&mut recents, "/c.md", 2);
+        assert_eq!(recents, vec!["/c.md", "/a.md"]);
+    }
+
+    #[test]
+    fn moved_path_follows_files_and_folder_contents() {
+        assert_eq!(
+            moved_path("/p/a.md", "/p/a.md", "/q/b.md").as_deref(),
+            Some("/q/b.md")
+        );
+        assert_eq!(
+            moved_path("/p/x/a.md", "/p/x", "/q/y").as_deref(),
+            Some("/q/y/a.md")
+        );
+        assert_eq!(moved_path("/p/xy/a.md", "/p/x", "/q/y"), None);
+    }
+
+    #[test]
+    fn rename_in_reports_changes() {
+        let mut list = vec!["/a.md".to_string(), "/b.md".to_string()];
+        assert!(rename_in(&mut list, "/b.md", "/c.md"));
+        assert_eq!(list, vec!["/a.md", "/c.md"]);
+        assert!(!rename_in(&mut list, "/b.md", "/d.md"));
+    }
+
+    #[test]
+    fn sanitize_file_name_strips_separators_and_adds_extension() {
+        assert_eq!(sanitize_file_name(" a/b: c ").as_deref(), Some("a-b- c.md"));
+        assert_eq!(sanitize_file_name("notes.MD").as_deref(), Some("notes.MD"));
+        assert_eq!(sanitize_file_name("..hidden").as_deref(), Some("hidden.md"));
+        assert_eq!(sanitize_file_name(" .. "), None);
+    }
+
+    #[test]
+    fn new_paper_content_adds_front_matter_only_with_tags() {
+        assert_eq!(new_paper_content("A", &[]), "# A\n");
+        let tags = vec!["ml".to_string(), "nlp".to_string()];
+        assert_eq!(
+            new_paper_content("A", &tags),
+            "---\ntags:\n  - \"ml\"\n  - \"nlp\"\n---\n\n# A\n"
+        );
+    }
+
+    #[test]
+    fn new_paper_content_quotes_tags() {
+        let tags = vec!["a, b".to_string(), "key: [x]".to_string()];
+        let content = new_paper_content("A", &tags);
+        assert_eq!(crate::frontmatter::parse_tags(&content), tags);
+
+        let content = new_paper_content("A", &["two\nlines".to_string()]);
+        assert!(content.starts_with("---\ntags:\n  - \"two\\nlines\"\n---\n"));
+    }
+
+    #[test]
+    fn search_open_log_keeps_newest_first() {
+        let entry = |query: &str| SearchOpen {
+            query: query.to_string(),
+            path: "/a.md".to_string(),
+            opened_ms: 0,
+        };
+        let mut log = vec![entry("old")];
+        push_search_open(&mut log, entry("new"), 2);
+        push_search_open(&mut log, entry("newest"), 2);
+        assert_eq!(log, vec![entry("newest"), entry("new")]);
+    }
+
+    #[test]
+    fn paper_text_is_capped_and_reports_lossy_decoding() {
+        let text = PaperText::read("# Title".as_bytes(), 7).unwrap();
+        assert_eq!(text.content, "# Title");
+        assert!(!text.lossy);
+
+        let text = PaperText::read(&b"caf\xe9"[..], 7).unwrap();
+        assert_eq!(text.content, "caf\u{fffd}");
+        assert!(text.lossy);
+
+        assert!(PaperText::read("# Title!".as_bytes(), 7).is_err());
+    }
+
+    #[test]
+    fn suggestions_match_prefixes_and_subsequences_most_recent_first() {
+        let history: Vec<String> = [
+            "attn",
+            "Attention",
+            "graph nets",
+            "attention",
+            "a tiny transformer",
+        ]
+        .iter()
+        .map(|q| q.to_string())
+        .collect();
+
+        assert_eq!(
+            suggest(&history, "att", 8),
+            vec!["attn", "Attention", "a tiny transformer"]
+        );
+        assert_eq!(
+            suggest(&history, "attn", 8),
+            vec!["Attention", "a tiny transformer"]
+        );
+        assert_eq!(suggest(&history, "", 2), vec!["attn", "Attention"]);
+        assert!(suggest(&history, "xyz", 8).is_empty());
+    }
+
+    #[test]
+    fn collection_roots_count_as_within_roots() {
+        let index = FileIndex::with_home_dir("/home/me".to_string());
+        index.set_roots(vec!["/home/me/papers".to_string()]);
+        index.set_collections(vec![file_search::Collection {
+            name: "shared".to_string(),
+            roots: vec!["/mnt/shared".to_string()],
+        }]);
+
+        assert!(check_within_roots(&index, &[Path::new("/home/me/papers/a.md")]).is_ok());
+        assert!(check_within_roots(&index, &[Path::new("/mnt/shared/b.md")]).is_ok());
+        assert!(check_within_roots(&index, &[Path::new("/etc/c.md")]).is_err());
+    }
+
+    #[test]
+    fn rename_no_replace_keeps_an_existing_target() {
+        let dir = std::env::temp_dir().join(format!("papers-rename-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let (a, b, c) = (dir.join("a.md"), dir.join("b.md"), dir.join("c.md"));
+        fs::write(&a, "a").unwrap();
+        fs::write(&b, "b").unwrap();
+
+        let err = rename_no_replace(&a, &b).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(&b).unwrap(), "b");
+
+        rename_no_replace(&a, &c).unwrap();
+        assert!(!a.exists());
+        assert_eq!(fs::read_to_string(&c).unwrap(), "a");
+
+        let (folder, moved) = (dir.join("folder"), dir.join("moved"));
+        fs::create_dir(&folder).unwrap();
+        assert_eq!(
+            rename_no_replace(&folder, &dir).unwrap_err().kind(),
+            io::ErrorKind::AlreadyExists
+        );
+        rename_no_replace(&folder, &moved).unwrap();
+        assert!(moved.is_dir() && !folder.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use tauri::{AppHandle, Manager};
+
+use crate::settings;
+use crate::tabs::TabManager;
+
+const OPEN_WITH_KEY: &str = "openWith";
+
+/// Placeholder replaced by the paper's path in a command template
+const PATH_PLACEHOLDER: &str = "{path}";
+
+/// An external program the paper can be opened with, e.g. `code {path}`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenWithEntry {
+    pub label: String,
+    pub command: String,
+}
+
+/// Split a command template into argv on whitespace, keeping double-quoted
+/// segments together (`"My Editor" --wait`), without involving a shell
+fn split_template(template: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quoted = false;
+
+    for c in template.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_arg = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+
+    if quoted {
+        return Err(format!("Unclosed quote in command: {}", template));
+    }
+    if in_arg {
+        args.push(current);
+    }
+    if args.is_empty() {
+        return Err("Command cannot be empty".to_string());
+    }
+    Ok(args)
+}
+
+/// The argv for opening a paper, with `{path}` substituted after splitting so a
+/// path containing spaces stays one argument
+fn command_args(template: &str, path: &str) -> Result<Vec<String>, String> {
+    Ok(split_template(template)?
+        .into_iter()
+        .map(|arg| arg.replace(PATH_PLACEHOLDER, path))
+        .collect())
+}
+
+fn validate(entries: &[OpenWithEntry]) -> Result<(), String> {
+    for (i, entry) in entries.iter().enumerate() {
+        if entry.label.trim().is_empty() {
+            return Err("Label cannot be empty".to_string());
+        }
+        if entries[..i].iter().any(|e| e.label == entry.label) {
+            return Err(format!("Duplicate label: {}", entry.label));
+        }
+        split_template(&entry.command)?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_open_with(app: AppHandle) -> Vec<OpenWithEntry> {
+    settings::load(&app, OPEN_WITH_KEY).unwrap_or_default()
+}
+
+/// Replace the configured "open with" programs
+#[tauri::command]
+pub fn set_open_with(app: AppHandle, entries: Vec<OpenWithEntry>) -> Result<(), String> {
+    validate(&entries)?;
+    settings::save(&app, OPEN_WITH_KEY, &entries)
+}
+
+/// Launch the "open with" program named `label` on the paper shown in a tab.
+///
+/// The program is spawned directly (not through a shell) and left running.
+#[tauri::command]
+pub fn run_open_with(app: AppHandle, id: String, label: String) -> Result<(), String> {
+    let tab = app
+        .state::<TabManager>()
+        .get_tab(&id)
+        .ok_or("Tab not found")?;
+    let path = tab.paper_path.ok_or("Tab has no paper")?;
+    let entry = get_open_with(app)
+        .into_iter()
+        .find(|e| e.label == label)
+        .ok_or_else(|| format!("No open with entry named {}", label))?;
+
+    let args = command_args(&entry.command, &path)?;
+    let mut child = Command::new(&args[0])
+        .args(&args[1..])
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", args[0], e))?;
+
+    // Reap the process when it exits so it doesn't linger as a zombie
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn template_splits_on_whitespace_and_quotes() {
+        assert_eq!(
+            split_template(r#"  "My Editor" --wait {path}"#).unwrap(),
+            vec!["My Editor", "--wait", "{path}"]
+        );
+        assert_eq!(
+            split_template(r#"open -a """#).unwrap(),
+            vec!["open", "-a", ""]
+        );
+        assert!(split_template("  ").is_err());
+        assert!(split_template(r#"code "{path}"#).is_err());
+    }
+
+    #[test]
+    fn path_stays_one_argument() {
+        assert_eq!(
+            command_args("code --goto {path}:1", "/p/my paper.md; rm -rf ~").unwrap(),
+            vec!["code", "--goto", "/p/my paper.md; rm -rf ~:1"]
+        );
+    }
+
+    #[test]
+    fn labels_must_be_unique_and_non_empty() {
+        let entry = |label: &str| OpenWithEntry {
+            label: label.to_string(),
+            command: "code {path}".to_string(),
+        };
+        assert!(validate(&[entry("Code"), entry("Zed")]).is_ok());
+        assert!(validate(&[entry("Code"), entry("Code")]).is_err());
+        assert!(validate(&[entry(" ")]).is_err());
+    }
+}
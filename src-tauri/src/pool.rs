@@ -1,33 +1,254 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use tauri::webview::WebviewBuilder;
-use tauri::{AppHandle, LogicalPosition, LogicalSize, Manager, WebviewUrl};
+use tauri::{AppHandle, Manager, WebviewUrl};
 use uuid::Uuid;
 
-use crate::tabs::TAB_BAR_HEIGHT;
+use crate::diagnostics;
+use crate::layout;
+use crate::settings;
+use crate::tabs;
 
+/// Default number of warm webviews kept ready for new tabs
 const POOL_SIZE: usize = 2;
 
+/// Upper bound on the pool size, since each warm webview costs memory
+const MAX_POOL_SIZE: usize = 8;
+
+const POOL_SIZE_KEY: &str = "poolSize";
+
+const NAVIGATE_IN_PLACE_KEY: &str = "poolNavigateInPlace";
+
+const PREWARM_KEY: &str = "poolPrewarm";
+
+/// How long a claimed webview has to acknowledge an in-place navigation before it's
+/// navigated with a full page load instead
+pub const NAVIGATE_ACK_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// How long `ensure_pool_warm` keeps creating webviews before returning what it has
+const ENSURE_WARM_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A pool webview navigated to a paper ahead of time by `prefetch_paper`
+struct Prefetch {
+    label: String,
+    tab_type: String,
+    path: String,
+    /// Whether the page reported ready while still in the pool
+    ready: bool,
+}
+
+/// A webview taken from the pool
+pub struct Claimed {
+    pub label: String,
+    /// Whether it was prefetched to the requested paper (so it needs no configuring)
+    pub prefetched: bool,
+    /// Whether the prefetched paper has finished loading
+    pub ready: bool,
+}
+
 pub struct WebviewPool {
     available: Mutex<Vec<String>>,
+    /// Available webviews showing a paper instead of the home view, oldest first
+    prefetched: Mutex<Vec<Prefetch>>,
+    /// How many webviews the pool is replenished to
+    target_size: AtomicUsize,
+    /// Slots reserved for webviews still being created (changed under `available`'s lock)
+    creating: AtomicUsize,
+    /// Whether warm webviews are created at all; off for low-memory machines, so
+    /// every tab gets a fresh webview
+    prewarm: AtomicBool,
+    /// Route claimed webviews through their router (see `tabs::route_claimed_webview`)
+    /// instead of only swapping the tab params
+    navigate_in_place: AtomicBool,
+    /// Claimed webviews navigated in place that haven't acknowledged it
+    awaiting_ack: Mutex<HashSet<String>>,
 }
 
 impl WebviewPool {
     pub fn new() -> Self {
         Self {
             available: Mutex::new(Vec::with_capacity(POOL_SIZE)),
+            prefetched: Mutex::new(Vec::new()),
+            target_size: AtomicUsize::new(POOL_SIZE),
+            creating: AtomicUsize::new(0),
+            prewarm: AtomicBool::new(true),
+            navigate_in_place: AtomicBool::new(false),
+            awaiting_ack: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn navigate_in_place(&self) -> bool {
+        self.navigate_in_place.load(Ordering::Relaxed)
+    }
+
+    fn set_navigate_in_place(&self, enabled: bool) {
+        self.navigate_in_place.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Note that a webview was navigated in place
+    pub fn expect_ack(&self, label: &str) {
+        self.awaiting_ack.lock().unwrap().insert(label.to_string());
+    }
+
+    /// Stop waiting for a webview's acknowledgement, returning false if it wasn't
+    /// awaited (already acknowledged, or already timed out)
+    pub fn take_ack(&self, label: &str) -> bool {
+        self.awaiting_ack.lock().unwrap().remove(label)
+    }
+
+    pub fn target_size(&self) -> usize {
+        self.target_size.load(Ordering::Relaxed)
+    }
+
+    fn set_target_size(&self, size: usize) {
+        self.target_size.store(size, Ordering::Relaxed);
+    }
+
+    pub fn prewarm(&self) -> bool {
+        self.prewarm.load(Ordering::Relaxed)
+    }
+
+    fn set_prewarm(&self, enabled: bool) {
+        self.prewarm.store(enabled, Ordering::Relaxed);
+    }
+
+    /// How many webviews to create to reach the target size (none with prewarm off),
+    /// not counting ones already being created
+    fn missing(&self, pool: &[String]) -> usize {
+        if !self.prewarm() {
+            return 0;
+        }
+        let pending = pool.len() + self.creating.load(Ordering::Relaxed);
+        self.target_size().saturating_sub(pending)
+    }
+
+    /// Reserve a slot for a webview about to be created, returning false once the
+    /// pool and the webviews in flight reach the target size.
+    ///
+    /// Concurrent fillers each reserve before creating, so together they can't
+    /// overfill the pool. Every reservation ends with `fill` or `release`.
+    fn reserve(&self) -> bool {
+        let pool = self.available.lock().unwrap();
+        if self.missing(&pool) == 0 {
+            return false;
+        }
+        self.creating.fetch_add(1, Ordering::Relaxed);
+        true
+    }
+
+    /// Give up a reservation whose webview couldn't be created
+    fn release(&self) {
+        let _pool = self.available.lock().unwrap();
+        let _ = self
+            .creating
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1));
+    }
+
+    /// Add the webview created for a reservation, returning false if the pool
+    /// shrank meanwhile and it's surplus (the caller closes it)
+    fn fill(&self, label: String) -> bool {
+        let mut pool = self.available.lock().unwrap();
+        let _ = self
+            .creating
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1));
+        if pool.len() >= self.target_size() {
+            return false;
         }
+        pool.push(label);
+        true
+    }
+
+    /// Whether webviews in flight still hold reservations
+    fn is_creating(&self) -> bool {
+        self.creating.load(Ordering::Relaxed) > 0
     }
 
     /// Claim a webview from the pool. Returns the label if available.
     pub fn claim(&self) -> Option<String> {
-        let mut pool = self.available.lock().unwrap();
-        pool.pop()
+        self.claim_for("home", None).map(|c| c.label)
     }
 
-    /// Add a webview label to the pool.
-    pub fn add(&self, label: String) {
+    /// Claim a webview for a tab, preferring one prefetched to the same paper.
+    ///
+    /// Otherwise a webview still on the home view is taken so other prefetches survive.
+    pub fn claim_for(&self, tab_type: &str, path: Option<&str>) -> Option<Claimed> {
         let mut pool = self.available.lock().unwrap();
-        pool.push(label);
+        let mut prefetched = self.prefetched.lock().unwrap();
+
+        let matching = prefetched
+            .iter()
+            .position(|p| p.tab_type == tab_type && Some(p.path.as_str()) == path);
+        if let Some(i) = matching {
+            let prefetch = prefetched.remove(i);
+            pool.retain(|l| *l != prefetch.label);
+            return Some(Claimed {
+                label: prefetch.label,
+                prefetched: true,
+                ready: prefetch.ready,
+            });
+        }
+
+        let i = pool
+            .iter()
+            .rposition(|l| !prefetched.iter().any(|p| p.label == *l))
+            .or(pool.len().checked_sub(1))?;
+        let label = pool.remove(i);
+        prefetched.retain(|p| p.label != label);
+        Some(Claimed {
+            label,
+            prefetched: false,
+            ready: false,
+        })
+    }
+
+    /// Pick a webview to prefetch a paper into, recording the prefetch.
+    ///
+    /// Prefers one still on the home view, then the oldest prefetch. Returns None if
+    /// the paper is already prefetched or the pool is empty.
+    pub fn prefetch(&self, tab_type: &str, path: &str) -> Option<String> {
+        let pool = self.available.lock().unwrap();
+        let mut prefetched = self.prefetched.lock().unwrap();
+        if prefetched
+            .iter()
+            .any(|p| p.tab_type == tab_type && p.path == path)
+        {
+            return None;
+        }
+
+        let label = match pool
+            .iter()
+            .rfind(|l| !prefetched.iter().any(|p| p.label == **l))
+        {
+            Some(label) => label.clone(),
+            None if prefetched.is_empty() => return None,
+            None => prefetched.remove(0).label,
+        };
+        prefetched.push(Prefetch {
+            label: label.clone(),
+            tab_type: tab_type.to_string(),
+            path: path.to_string(),
+            ready: false,
+        });
+        Some(label)
+    }
+
+    /// Note that a prefetched page finished loading, returning false for other labels
+    pub fn mark_prefetch_ready(&self, label: &str) -> bool {
+        let mut prefetched = self.prefetched.lock().unwrap();
+        match prefetched.iter_mut().find(|p| p.label == label) {
+            Some(prefetch) => {
+                prefetch.ready = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether a webview label is waiting in the pool.
+    pub fn contains(&self, label: &str) -> bool {
+        self.available.lock().unwrap().iter().any(|l| l == label)
     }
 
     /// Get current pool size.
@@ -42,57 +263,250 @@ pub fn create_pooled_webview(app: &AppHandle) -> Result<String, String> {
 
     let window = app.get_window("main").ok_or("Main window not found")?;
 
-    // Get window dimensions for sizing
-    let size = window.inner_size().map_err(|e| e.to_string())?;
-    let scale = window.scale_factor().unwrap_or(1.0);
-
-    let width = size.width as f64 / scale;
-    let height = (size.height as f64 / scale) - TAB_BAR_HEIGHT;
+    // Get content area for sizing
+    let (position, size) = layout::content_bounds(app, &window)?;
 
     // Pool webviews load the home view (bundle pre-loaded)
     let url = WebviewUrl::App("/tab?type=home".into());
-    let webview_builder = WebviewBuilder::new(&label, url);
-
-    let position = LogicalPosition::new(0.0, TAB_BAR_HEIGHT);
-    let webview_size = LogicalSize::new(width, height);
+    // Marks the tab loading again on full navigations once this webview belongs to one
+    let webview_builder = WebviewBuilder::new(&label, url).on_page_load(tabs::on_page_load);
 
     let webview = window
-        .add_child(webview_builder, position, webview_size)
+        .add_child(webview_builder, position, size)
         .map_err(|e| e.to_string())?;
 
     // Hide the pooled webview initially
     let _ = webview.hide();
 
-    log::info!("Created pooled webview: {}", label);
+    let message = format!("Created pooled webview: {}", label);
+    diagnostics::record(app, log::Level::Info, "pool", message);
 
     Ok(label)
 }
 
-/// Initialize the pool with POOL_SIZE webviews.
+/// Initialize the pool with the persisted settings (POOL_SIZE webviews by default).
+///
+/// The webviews are created in the background, after the initial tab, so they
+/// don't hold up the first paint; tabs opened before then get fresh webviews.
 pub fn initialize_pool(app: &AppHandle) {
     let pool = app.state::<WebviewPool>();
 
-    for _ in 0..POOL_SIZE {
-        match create_pooled_webview(app) {
-            Ok(label) => pool.add(label),
-            Err(e) => log::error!("Failed to create pooled webview: {}", e),
-        }
+    // Older stores may hold a size from before the clamp existed
+    if let Some(size) = settings::load::<usize>(app, POOL_SIZE_KEY) {
+        pool.set_target_size(size.min(MAX_POOL_SIZE));
+    }
+    if let Some(enabled) = settings::load::<bool>(app, NAVIGATE_IN_PLACE_KEY) {
+        pool.set_navigate_in_place(enabled);
     }
+    if let Some(enabled) = settings::load::<bool>(app, PREWARM_KEY) {
+        pool.set_prewarm(enabled);
+    }
+
+    replenish_pool(app.clone());
+}
 
-    log::info!("Initialized webview pool with {} webviews", pool.size());
+/// Create a webview for a slot taken with `reserve` and put it in the pool,
+/// closing it again if the pool shrank in the meantime
+fn fill_reserved(app: &AppHandle, pool: &WebviewPool) -> Result<(), String> {
+    let label = create_pooled_webview(app).inspect_err(|_| pool.release())?;
+    if !pool.fill(label.clone()) {
+        if let Some(webview) = app.get_webview(&label) {
+            let _ = webview.close();
+        }
+    }
+    Ok(())
 }
 
-/// Replenish the pool back to POOL_SIZE (runs async after claim).
+/// Replenish the pool back to its target size (runs async after claim).
 pub fn replenish_pool(app: AppHandle) {
     tauri::async_runtime::spawn(async move {
         let pool = app.state::<WebviewPool>();
-        let current_size = pool.size();
-
-        for _ in current_size..POOL_SIZE {
-            match create_pooled_webview(&app) {
-                Ok(label) => pool.add(label),
-                Err(e) => log::error!("Failed to replenish pooled webview: {}", e),
+        while pool.reserve() {
+            if let Err(e) = fill_reserved(&app, &pool) {
+                let message = format!("Failed to replenish pooled webview: {}", e);
+                diagnostics::record(&app, log::Level::Error, "pool", message);
+                break;
             }
         }
     });
 }
+
+/// Top the pool up to its target size and return once done, unlike the
+/// fire-and-forget `replenish_pool`, e.g. before opening many papers at once.
+///
+/// Gives up creating webviews after `ENSURE_WARM_TIMEOUT`. Returns how many are
+/// available (0 with prewarm off).
+#[tauri::command]
+pub async fn ensure_pool_warm(app: AppHandle) -> Result<usize, String> {
+    let pool = app.state::<WebviewPool>();
+    let started = Instant::now();
+    // A background replenish may be filling it too, so slots are reserved one at a
+    // time and the ones it holds are waited for
+    while started.elapsed() < ENSURE_WARM_TIMEOUT {
+        if pool.reserve() {
+            fill_reserved(&app, &pool)?;
+        } else if pool.is_creating() {
+            std::thread::sleep(Duration::from_millis(10));
+        } else {
+            break;
+        }
+    }
+    Ok(pool.size())
+}
+
+#[tauri::command]
+pub fn get_pool_size(app: AppHandle) -> usize {
+    app.state::<WebviewPool>().target_size()
+}
+
+/// Change how many warm webviews are kept, clamped to MAX_POOL_SIZE.
+///
+/// Growing fills the pool in the background; shrinking closes the surplus.
+/// Returns the size actually applied.
+#[tauri::command]
+pub fn set_pool_size(app: AppHandle, size: usize) -> Result<usize, String> {
+    let size = size.min(MAX_POOL_SIZE);
+    let pool = app.state::<WebviewPool>();
+    pool.set_target_size(size);
+    settings::save(&app, POOL_SIZE_KEY, &size)?;
+
+    shrink_pool(&app, size);
+    replenish_pool(app.clone());
+    Ok(size)
+}
+
+/// Close pooled webviews until at most `size` are left
+fn shrink_pool(app: &AppHandle, size: usize) {
+    let pool = app.state::<WebviewPool>();
+    while pool.size() > size {
+        let Some(label) = pool.claim() else {
+            break;
+        };
+        if let Some(webview) = app.get_webview(&label) {
+            let _ = webview.close();
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_pool_prewarm(app: AppHandle) -> bool {
+    app.state::<WebviewPool>().prewarm()
+}
+
+/// Keep warm webviews ready for new tabs, or (for low-memory machines) close them
+/// and create every tab's webview fresh. The pool size is kept for re-enabling.
+#[tauri::command]
+pub fn set_pool_prewarm(app: AppHandle, enabled: bool) -> Result<(), String> {
+    app.state::<WebviewPool>().set_prewarm(enabled);
+    settings::save(&app, PREWARM_KEY, &enabled)?;
+    if enabled {
+        replenish_pool(app);
+    } else {
+        shrink_pool(&app, 0);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_pool_navigate_in_place(app: AppHandle) -> bool {
+    app.state::<WebviewPool>().navigate_in_place()
+}
+
+/// Let claimed webviews switch route in place through the SPA router (falling
+/// back to a full load if they don't acknowledge), instead of the eval handshake
+#[tauri::command]
+pub fn set_pool_navigate_in_place(app: AppHandle, enabled: bool) -> Result<(), String> {
+    app.state::<WebviewPool>().set_navigate_in_place(enabled);
+    settings::save(&app, NAVIGATE_IN_PLACE_KEY, &enabled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool_with(labels: &[&str]) -> WebviewPool {
+        let pool = WebviewPool::new();
+        for label in labels {
+            assert!(pool.reserve() && pool.fill(label.to_string()));
+        }
+        pool
+    }
+
+    #[test]
+    fn claim_prefers_prefetched_paper() {
+        let pool = pool_with(&["pool-a", "pool-b"]);
+        assert_eq!(
+            pool.prefetch("markdown", "/p/x.md").as_deref(),
+            Some("pool-b")
+        );
+        assert_eq!(pool.prefetch("markdown", "/p/x.md"), None);
+        assert!(pool.mark_prefetch_ready("pool-b"));
+
+        let claimed = pool.claim_for("markdown", Some("/p/x.md")).unwrap();
+        assert_eq!(claimed.label, "pool-b");
+        assert!(claimed.prefetched && claimed.ready);
+        assert_eq!(pool.size(), 1);
+    }
+
+    #[test]
+    fn other_claims_leave_prefetches_until_none_else_is_free() {
+        let pool = pool_with(&["pool-a", "pool-b"]);
+        pool.prefetch("markdown", "/p/x.md");
+
+        let claimed = pool.claim_for("home", None).unwrap();
+        assert_eq!(claimed.label, "pool-a");
+        assert!(!claimed.prefetched);
+
+        // Only the prefetched webview is left, so a new prefetch retargets it
+        assert_eq!(
+            pool.prefetch("markdown", "/p/y.md").as_deref(),
+            Some("pool-b")
+        );
+        let claimed = pool.claim_for("markdown", Some("/p/x.md")).unwrap();
+        assert_eq!(claimed.label, "pool-b");
+        assert!(!claimed.prefetched);
+        assert!(pool.claim().is_none());
+    }
+
+    #[test]
+    fn prewarm_off_leaves_the_pool_empty() {
+        let pool = pool_with(&["pool-a"]);
+        assert!(pool.reserve());
+        pool.release();
+
+        pool.set_prewarm(false);
+        assert!(!pool.reserve());
+    }
+
+    #[test]
+    fn reservations_keep_concurrent_fills_within_the_target() {
+        let pool = pool_with(&["pool-a"]);
+        assert!(pool.reserve());
+        // The one missing webview is already being created
+        assert!(!pool.reserve());
+        assert!(pool.fill("pool-b".to_string()));
+        assert!(!pool.is_creating());
+
+        // Shrinking while a webview is created makes it surplus
+        pool.claim();
+        assert!(pool.reserve());
+        pool.set_target_size(1);
+        assert!(!pool.fill("pool-c".to_string()));
+        assert_eq!(pool.size(), 1);
+        pool.set_target_size(2);
+        assert!(pool.reserve());
+        pool.release();
+        assert!(pool.reserve());
+    }
+
+    #[test]
+    fn navigation_ack_is_taken_once() {
+        let pool = WebviewPool::new();
+        pool.expect_ack("pool-a");
+
+        assert!(pool.take_ack("pool-a"));
+        // A late timeout (or a second ack) finds nothing left to do
+        assert!(!pool.take_ack("pool-a"));
+        assert!(!pool.take_ack("pool-b"));
+    }
+}
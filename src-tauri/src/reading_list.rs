@@ -0,0 +1,190 @@
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::library;
+use crate::settings;
+use crate::tabs;
+
+const READING_LIST_KEY: &str = "readingList";
+
+/// Papers queued to read later without opening a tab for each, persisted across restarts
+pub struct ReadingListStore(Mutex<Vec<String>>);
+
+impl ReadingListStore {
+    pub fn new() -> Self {
+        Self(Mutex::new(Vec::new()))
+    }
+
+    pub fn list(&self) -> Vec<String> {
+        self.0.lock().unwrap().clone()
+    }
+
+    fn replace(&self, queue: Vec<String>) {
+        *self.0.lock().unwrap() = queue;
+    }
+
+    /// Add a path to the back of the queue, returning false if it's already queued
+    pub fn enqueue(&self, path: &str) -> bool {
+        let mut queue = self.0.lock().unwrap();
+        if queue.iter().any(|p| p == path) {
+            return false;
+        }
+        queue.push(path.to_string());
+        true
+    }
+
+    /// Remove a path from the queue, returning false if it wasn't queued
+    pub fn dequeue(&self, path: &str) -> bool {
+        let mut queue = self.0.lock().unwrap();
+        let len = queue.len();
+        queue.retain(|p| p != path);
+        queue.len() != len
+    }
+
+    /// The paper at the front of the queue, left in place
+    pub fn front(&self) -> Option<String> {
+        self.0.lock().unwrap().first().cloned()
+    }
+
+    /// Rewrite queued paths under a moved file or folder, returning true if any changed
+    fn rename_path(&self, old: &str, new: &str) -> bool {
+        let mut queue = self.0.lock().unwrap();
+        let mut changed = false;
+        for path in queue.iter_mut() {
+            if let Some(moved) = library::moved_path(path, old, new) {
+                *path = moved;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Drop queued paths that no longer exist, returning how many were removed
+    pub fn prune(&self, exists: impl Fn(&str) -> bool) -> usize {
+        let mut queue = self.0.lock().unwrap();
+        let len = queue.len();
+        queue.retain(|p| exists(p));
+        len - queue.len()
+    }
+}
+
+fn path_exists(path: &str) -> bool {
+    Path::new(path).exists()
+}
+
+fn save_queue(app: &AppHandle) -> Result<(), String> {
+    settings::save(
+        app,
+        READING_LIST_KEY,
+        &app.state::<ReadingListStore>().list(),
+    )
+}
+
+/// Restore the reading list from the store, dropping deleted papers (called at startup)
+pub fn load_reading_list(app: &AppHandle) {
+    let Some(queue) = settings::load::<Vec<String>>(app, READING_LIST_KEY) else {
+        return;
+    };
+
+    let store = app.state::<ReadingListStore>();
+    store.replace(queue);
+    if store.prune(path_exists) > 0 {
+        if let Err(e) = save_queue(app) {
+            log::warn!("Failed to persist reading list: {}", e);
+        }
+    }
+}
+
+/// Follow a paper moved by `library::rename_paper`
+pub fn rename_path(app: &AppHandle, old: &str, new: &str) -> Result<(), String> {
+    if app.state::<ReadingListStore>().rename_path(old, new) {
+        save_queue(app)?;
+    }
+    Ok(())
+}
+
+/// Drop queued papers that were deleted, saving the queue if any were
+pub fn prune_missing(app: &AppHandle) -> Result<(), String> {
+    if app.state::<ReadingListStore>().prune(path_exists) > 0 {
+        save_queue(app)?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn enqueue(app: AppHandle, path: String) -> Result<(), String> {
+    if !path_exists(&path) {
+        return Err(format!("Paper not found: {}", path));
+    }
+    if app.state::<ReadingListStore>().enqueue(&path) {
+        save_queue(&app)?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn dequeue(app: AppHandle, path: String) -> Result<(), String> {
+    if app.state::<ReadingListStore>().dequeue(&path) {
+        save_queue(&app)?;
+    }
+    Ok(())
+}
+
+/// List the queued papers in reading order, dropping any that were deleted
+#[tauri::command]
+pub fn list_queue(app: AppHandle) -> Result<Vec<String>, String> {
+    prune_missing(&app)?;
+    Ok(app.state::<ReadingListStore>().list())
+}
+
+/// Open the paper at the front of the queue in a new tab.
+///
+/// Returns the new tab id, or None if the queue is empty. The paper only leaves the
+/// queue once its tab opened, so a failure keeps it queued.
+#[tauri::command]
+pub fn open_next_from_queue(app: AppHandle) -> Result<Option<String>, String> {
+    let store = app.state::<ReadingListStore>();
+    let pruned = store.prune(path_exists) > 0;
+    let Some(path) = store.front() else {
+        if pruned {
+            save_queue(&app)?;
+        }
+        return Ok(None);
+    };
+
+    let id = tabs::create_tab_with_pool(&app, tabs::tab_for_path(&path))?;
+    store.dequeue(&path);
+    save_queue(&app)?;
+    Ok(Some(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queue_keeps_order_and_ignores_duplicates() {
+        let store = ReadingListStore::new();
+        assert!(store.enqueue("/a.md"));
+        assert!(store.enqueue("/b.md"));
+        assert!(!store.enqueue("/a.md"));
+
+        assert_eq!(store.front().as_deref(), Some("/a.md"));
+        assert!(store.dequeue("/a.md"));
+        assert_eq!(store.list(), vec!["/b.md"]);
+        assert!(store.dequeue("/b.md"));
+        assert!(!store.dequeue("/b.md"));
+        assert_eq!(store.front(), None);
+    }
+
+    #[test]
+    fn prune_drops_missing_papers() {
+        let store = ReadingListStore::new();
+        store.enqueue("/a.md");
+        store.enqueue("/gone.md");
+
+        assert_eq!(store.prune(|p| p != "/gone.md"), 1);
+        assert_eq!(store.list(), vec!["/a.md"]);
+    }
+}
@@ -0,0 +1,537 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use tauri::{AppHandle, Manager};
+
+use crate::library;
+use crate::settings;
+use crate::tabs::{self, TabInfo, TabManager, TabState};
+
+const SESSION_KEY: &str = "session";
+
+/// Named snapshots saved with `save_session`
+const NAMED_SESSIONS_KEY: &str = "savedSessions";
+
+/// Format version written by `export_session`; bump it (and migrate older
+/// versions in `parse_export`) when the format changes
+const EXPORT_VERSION: u64 = 1;
+
+/// A reading set written to a file by `export_session`, to share or move between machines
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SessionExport {
+    version: u64,
+    tabs: Vec<ExportedTab>,
+    /// Index of the active tab
+    #[serde(default)]
+    active: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ExportedTab {
+    tab_type: String,
+    #[serde(default)]
+    path: Option<String>,
+    title: String,
+}
+
+/// Replace the `from` prefix of imported paths with `to`, e.g. another user's home directory
+#[derive(Debug, Clone, Deserialize)]
+pub struct PathRebase {
+    pub from: String,
+    pub to: String,
+}
+
+/// What `import_session` opened
+#[derive(Debug, Default, Serialize)]
+pub struct SessionImportReport {
+    pub opened: usize,
+    /// Paper paths (after rebasing) that don't exist here
+    pub missing_papers: Vec<String>,
+}
+
+/// What `repair_session` had to fix in the saved session
+#[derive(Debug, Default, Serialize)]
+pub struct SessionRepairReport {
+    /// Entries that couldn't be read as a tab or had an unknown type
+    pub invalid_tabs: usize,
+    /// Entries sharing an id with an earlier tab
+    pub duplicate_tabs: usize,
+    /// Paper paths that no longer exist
+    pub missing_papers: Vec<String>,
+    /// Whether the active tab had to be moved to a surviving tab
+    pub active_tab_reset: bool,
+}
+
+impl SessionRepairReport {
+    pub fn is_clean(&self) -> bool {
+        self.invalid_tabs == 0
+            && self.duplicate_tabs == 0
+            && self.missing_papers.is_empty()
+            && !self.active_tab_reset
+    }
+}
+
+/// Summary of the stored session returned by `validate_session`
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct SessionReport {
+    /// Tab entries in the store before repairing
+    pub total: usize,
+    /// Paper paths that no longer exist
+    pub missing: Vec<String>,
+    /// Whether anything had to be dropped or fixed (and the store rewritten)
+    pub repaired: bool,
+}
+
+impl SessionReport {
+    fn new(kept: usize, report: SessionRepairReport) -> Self {
+        Self {
+            total: kept + report.invalid_tabs + report.duplicate_tabs + report.missing_papers.len(),
+            repaired: !report.is_clean(),
+            missing: report.missing_papers,
+        }
+    }
+}
+
+/// The open tabs as they're saved (see `persistable`)
+fn snapshot(app: &AppHandle) -> TabState {
+    persistable(app.state::<TabManager>().get_state())
+}
+
+/// Drop ephemeral tabs (their backing files are deleted on close) and save a peek
+/// tab as a plain one, since the preview it stood for ends with the app
+fn persistable(mut state: TabState) -> TabState {
+    state.tabs.retain(|t| !t.ephemeral);
+    for tab in &mut state.tabs {
+        tab.peek = false;
+    }
+    state
+}
+
+/// Persist the open tabs so they can be restored on the next launch
+pub fn autosave(app: &AppHandle) {
+    if let Err(e) = settings::save(app, SESSION_KEY, &snapshot(app)) {
+        log::error!("Failed to save session: {}", e);
+    }
+}
+
+/// Read the saved session, dropping entries that can't be restored.
+///
+/// Each tab is read on its own so one corrupt entry doesn't discard the rest.
+fn repair(value: &Value, exists: impl Fn(&TabInfo) -> bool) -> (TabState, SessionRepairReport) {
+    let mut report = SessionRepairReport::default();
+    let mut seen = HashSet::new();
+    let mut tabs = Vec::new();
+
+    let entries = value.get("tabs").and_then(Value::as_array);
+    for entry in entries.into_iter().flatten() {
+        let tab = match serde_json::from_value::<TabInfo>(entry.clone()) {
+            Ok(tab) if is_valid(&tab) => tab,
+            _ => {
+                report.invalid_tabs += 1;
+                continue;
+            }
+        };
+        if !seen.insert(tab.id.clone()) {
+            report.duplicate_tabs += 1;
+            continue;
+        }
+        if !exists(&tab) {
+            report.missing_papers.extend(tab.paper_path);
+            continue;
+        }
+        tabs.push(tab);
+    }
+
+    let saved_active = value
+        .get("active_tab_id")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let active_tab_id = if tabs.iter().any(|t| t.id == saved_active) {
+        saved_active.to_string()
+    } else {
+        let fallback = tabs.first().map(|t| t.id.clone()).unwrap_or_default();
+        report.active_tab_reset = fallback != saved_active;
+        fallback
+    };
+
+    (
+        TabState {
+            tabs,
+            active_tab_id,
+        },
+        report,
+    )
+}
+
+fn is_valid(tab: &TabInfo) -> bool {
+    if tab.id.is_empty() {
+        return false;
+    }
+    match tab.tab_type.as_str() {
+        "home" => true,
+        "paper" | "markdown" => tab.paper_path.is_some(),
+        _ => false,
+    }
+}
+
+/// Repair the stored session and write the cleaned version back
+fn load_repaired(app: &AppHandle) -> Result<Option<(TabState, SessionRepairReport)>, String> {
+    let Some(value) = settings::load::<Value>(app, SESSION_KEY) else {
+        return Ok(None);
+    };
+
+    let (state, report) = repair(&value, tabs::can_reopen);
+    if !report.is_clean() {
+        settings::save(app, SESSION_KEY, &state)?;
+    }
+    Ok(Some((state, report)))
+}
+
+/// Read the session saved by the previous launch, repairing it if needed.
+///
+/// Must be called before the initial tab is created, since every tab state
+/// change overwrites the saved session.
+pub fn load_autosave(app: &AppHandle) -> Option<TabState> {
+    match load_repaired(app) {
+        Ok(Some((state, report))) => {
+            if !report.is_clean() {
+                log::warn!("Repaired saved session: {:?}", report);
+            }
+            Some(state)
+        }
+        Ok(None) => None,
+        Err(e) => {
+            log::error!("Failed to repair saved session: {}", e);
+            None
+        }
+    }
+}
+
+/// Replace the open tabs with a saved session.
+///
+/// Tab ids are reassigned, so the previously active tab is matched by position.
+fn open_saved(app: &AppHandle, saved: TabState) -> Result<(), String> {
+    let active = saved
+        .tabs
+        .iter()
+        .position(|t| t.id == saved.active_tab_id)
+        .unwrap_or(0);
+    tabs::replace_tabs(app, saved.tabs, active)
+}
+
+/// Reopen the tabs saved by the previous launch in place of the initial home tab
+pub fn restore_session(app: &AppHandle, saved: TabState) {
+    if saved.tabs.is_empty() {
+        return;
+    }
+    if let Err(e) = open_saved(app, saved) {
+        log::error!("Failed to restore session: {}", e);
+    }
+}
+
+fn named_sessions(app: &AppHandle) -> BTreeMap<String, Value> {
+    settings::load(app, NAMED_SESSIONS_KEY).unwrap_or_default()
+}
+
+/// Save the open tabs (order, titles, themes and colors) under a name
+#[tauri::command]
+pub fn save_session(app: AppHandle, name: String) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Session name cannot be empty".to_string());
+    }
+
+    let snapshot = serde_json::to_value(snapshot(&app)).map_err(|e| e.to_string())?;
+    let mut sessions = named_sessions(&app);
+    sessions.insert(name.to_string(), snapshot);
+    settings::save(&app, NAMED_SESSIONS_KEY, &sessions)
+}
+
+/// Names of the saved sessions, sorted
+#[tauri::command]
+pub fn list_sessions(app: AppHandle) -> Vec<String> {
+    named_sessions(&app).into_keys().collect()
+}
+
+/// Close the open tabs and reopen a saved session.
+///
+/// Papers deleted since the session was saved are skipped.
+#[tauri::command]
+pub fn load_session(app: AppHandle, name: String) -> Result<(), String> {
+    let sessions = named_sessions(&app);
+    let value = sessions
+        .get(name.trim())
+        .ok_or_else(|| format!("No saved session named {}", name))?;
+
+    let (saved, report) = repair(value, tabs::can_reopen);
+    if !report.is_clean() {
+        log::warn!("Repaired saved session {}: {:?}", name, report);
+    }
+    if saved.tabs.is_empty() {
+        return Err(format!("None of the tabs in {} can be reopened", name));
+    }
+    open_saved(&app, saved)
+}
+
+/// Clean up the stored session: drop unreadable, duplicate and missing-paper tabs
+/// and point the active tab at a survivor.
+#[tauri::command]
+pub fn repair_session(app: AppHandle) -> Result<SessionRepairReport, String> {
+    Ok(load_repaired(&app)?
+        .map(|(_, report)| report)
+        .unwrap_or_default())
+}
+
+/// The exportable part of the open tabs: type, path and title, in order
+fn to_export(state: &TabState) -> SessionExport {
+    SessionExport {
+        version: EXPORT_VERSION,
+        tabs: state
+            .tabs
+            .iter()
+            .map(|t| ExportedTab {
+                tab_type: t.tab_type.clone(),
+                path: t.paper_path.clone(),
+                title: t.title.clone(),
+            })
+            .collect(),
+        active: state
+            .tabs
+            .iter()
+            .position(|t| t.id == state.active_tab_id)
+            .unwrap_or(0),
+    }
+}
+
+/// Read an export, rejecting anything that isn't one or is from a newer version
+fn parse_export(text: &str) -> Result<SessionExport, String> {
+    let value: Value =
+        serde_json::from_str(text).map_err(|e| format!("Not a session export: {}", e))?;
+    let version = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or("Not a session export: missing version")?;
+    if version > EXPORT_VERSION {
+        return Err(format!(
+            "Session export version {} is newer than this app supports",
+            version
+        ));
+    }
+
+    let export: SessionExport =
+        serde_json::from_value(value).map_err(|e| format!("Invalid session export: {}", e))?;
+    for tab in &export.tabs {
+        let valid = match tab.tab_type.as_str() {
+            "home" => true,
+            "paper" | "markdown" => tab.path.is_some(),
+            _ => false,
+        };
+        if !valid {
+            return Err(format!("Invalid tab in session export: {}", tab.title));
+        }
+    }
+    Ok(export)
+}
+
+/// Turn an export into tabs to open, rebasing paths and skipping missing papers.
+///
+/// Returns the tabs and the index of the active one; if the active tab was skipped,
+/// the next surviving tab takes its place.
+fn import_tabs(
+    export: SessionExport,
+    rebase: Option<&PathRebase>,
+    exists: impl Fn(&TabInfo) -> bool,
+    report: &mut SessionImportReport,
+) -> (Vec<TabInfo>, usize) {
+    let mut tabs = Vec::new();
+    let mut active = 0;
+    for (i, tab) in export.tabs.into_iter().enumerate() {
+        let path = match (tab.path, rebase) {
+            (Some(path), Some(r)) => {
+                Some(library::moved_path(&path, &r.from, &r.to).unwrap_or(path))
+            }
+            (path, _) => path,
+        };
+        let tab = TabInfo::new(&tab.tab_type, path, tab.title);
+        if !exists(&tab) {
+            report.missing_papers.extend(tab.paper_path);
+            continue;
+        }
+        if i < export.active {
+            active += 1;
+        }
+        tabs.push(tab);
+    }
+    report.opened = tabs.len();
+    (tabs, active)
+}
+
+/// Write the open tabs (types, paths, titles and order) to a JSON file at `dest`
+#[tauri::command]
+pub fn export_session(app: AppHandle, dest: String) -> Result<(), String> {
+    let export = to_export(&snapshot(&app));
+    let json = serde_json::to_string_pretty(&export).map_err(|e| e.to_string())?;
+    std::fs::write(&dest, json).map_err(|e| format!("Failed to write {}: {}", dest, e))
+}
+
+/// Close the open tabs and open the ones in a file written by `export_session`.
+///
+/// Paths starting with `rebase.from` are moved under `rebase.to` first; papers
+/// that don't exist here are skipped and listed in the report.
+#[tauri::command]
+pub fn import_session(
+    app: AppHandle,
+    src: String,
+    rebase: Option<PathRebase>,
+) -> Result<SessionImportReport, String> {
+    let text =
+        std::fs::read_to_string(&src).map_err(|e| format!("Failed to read {}: {}", src, e))?;
+    let export = parse_export(&text)?;
+
+    let mut report = SessionImportReport::default();
+    let (tabs, active) = import_tabs(export, rebase.as_ref(), tabs::can_reopen, &mut report);
+    if tabs.is_empty() {
+        return Err(format!("None of the tabs in {} can be opened", src));
+    }
+    tabs::replace_tabs(&app, tabs, active)?;
+    Ok(report)
+}
+
+/// Check the stored session and drop tabs that can't be restored, as startup does
+/// before restoring (see `load_autosave`), for a manual "clean up" action
+#[tauri::command]
+pub fn validate_session(app: AppHandle) -> Result<SessionReport, String> {
+    Ok(load_repaired(&app)?
+        .map(|(state, report)| SessionReport::new(state.tabs.len(), report))
+        .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn paper(id: &str, path: &str) -> Value {
+        json!({ "id": id, "tab_type": "paper", "paper_path": path, "title": id })
+    }
+
+    #[test]
+    fn repair_keeps_a_clean_session() {
+        let value = json!({ "tabs": [paper("a", "/p/a")], "active_tab_id": "a" });
+        let (state, report) = repair(&value, |_| true);
+        assert!(report.is_clean());
+        assert_eq!(state.tabs.len(), 1);
+        assert_eq!(state.active_tab_id, "a");
+    }
+
+    #[test]
+    fn repair_drops_invalid_duplicate_and_missing_tabs() {
+        let value = json!({
+            "tabs": [
+                paper("a", "/p/a"),
+                { "id": "b", "tab_type": "paper", "title": "no path" },
+                42,
+                paper("a", "/p/a-again"),
+                paper("c", "/p/deleted"),
+                paper("d", "/p/d"),
+            ],
+            "active_tab_id": "c",
+        });
+        let (state, report) = repair(&value, |t| t.paper_path.as_deref() != Some("/p/deleted"));
+
+        let ids: Vec<_> = state.tabs.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "d"]);
+        assert_eq!(report.invalid_tabs, 2);
+        assert_eq!(report.duplicate_tabs, 1);
+        assert_eq!(report.missing_papers, vec!["/p/deleted"]);
+        assert!(report.active_tab_reset);
+        assert_eq!(state.active_tab_id, "a");
+
+        let summary = SessionReport::new(state.tabs.len(), report);
+        assert_eq!(summary.total, 6);
+        assert_eq!(summary.missing, vec!["/p/deleted"]);
+        assert!(summary.repaired);
+    }
+
+    #[test]
+    fn export_round_trips_and_rejects_bad_files() {
+        let state: TabState = serde_json::from_value(json!({
+            "tabs": [json!({ "id": "h", "tab_type": "home", "title": "Library" }), paper("a", "/p/a")],
+            "active_tab_id": "a",
+        }))
+        .unwrap();
+        let export = to_export(&state);
+        let text = serde_json::to_string(&export).unwrap();
+        assert_eq!(parse_export(&text).unwrap(), export);
+        assert_eq!(export.active, 1);
+
+        assert!(parse_export("[1, 2]").is_err());
+        assert!(parse_export(r#"{ "version": 99, "tabs": [] }"#).is_err());
+        let no_path = r#"{ "version": 1, "tabs": [{ "tab_type": "paper", "title": "x" }] }"#;
+        assert!(parse_export(no_path).is_err());
+    }
+
+    #[test]
+    fn import_rebases_paths_and_skips_missing_papers() {
+        let tab = |path: &str| ExportedTab {
+            tab_type: "markdown".to_string(),
+            path: Some(path.to_string()),
+            title: path.to_string(),
+        };
+        let export = SessionExport {
+            version: EXPORT_VERSION,
+            tabs: vec![
+                tab("/home/ann/gone.md"),
+                tab("/home/ann/a.md"),
+                tab("/srv/b.md"),
+            ],
+            active: 0,
+        };
+        let rebase = PathRebase {
+            from: "/home/ann".to_string(),
+            to: "/home/me".to_string(),
+        };
+        let mut report = SessionImportReport::default();
+        let exists = |t: &TabInfo| !t.paper_path.as_deref().unwrap().ends_with("gone.md");
+        let (tabs, active) = import_tabs(export, Some(&rebase), exists, &mut report);
+
+        let paths: Vec<_> = tabs
+            .iter()
+            .filter_map(|t| t.paper_path.as_deref())
+            .collect();
+        assert_eq!(paths, vec!["/home/me/a.md", "/srv/b.md"]);
+        assert_eq!(active, 0);
+        assert_eq!(report.opened, 2);
+        assert_eq!(report.missing_papers, vec!["/home/me/gone.md"]);
+    }
+
+    #[test]
+    fn repair_survives_an_unreadable_store() {
+        let (state, report) = repair(&json!("garbage"), |_| true);
+        assert!(state.tabs.is_empty());
+        assert!(report.is_clean());
+    }
+
+    #[test]
+    fn saved_sessions_drop_ephemeral_and_peek_state() {
+        let stdin = TabInfo {
+            ephemeral: true,
+            ..TabInfo::new(
+                "markdown",
+                Some("/tmp/stdin.md".to_string()),
+                "stdin".to_string(),
+            )
+        };
+        let peek = TabInfo {
+            peek: true,
+            ..TabInfo::new("markdown", Some("/p/a.md".to_string()), "a".to_string())
+        };
+        let state = persistable(TabState {
+            tabs: vec![stdin, peek],
+            active_tab_id: String::new(),
+        });
+
+        assert_eq!(state.tabs.len(), 1);
+        assert!(!state.tabs[0].peek);
+    }
+}
@@ -0,0 +1,28 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+/// Store file shared with the frontend's `LazyStore`
+pub const STORE_FILE: &str = "settings.json";
+
+/// Read a value from the persisted store, if present and well-formed
+pub fn load<T: DeserializeOwned>(app: &AppHandle, key: &str) -> Option<T> {
+    let store = app.store(STORE_FILE).ok()?;
+    serde_json::from_value(store.get(key)?).ok()
+}
+
+/// Write a value to the persisted store (flushed by the store's auto-save)
+pub fn save<T: Serialize>(app: &AppHandle, key: &str, value: &T) -> Result<(), String> {
+    let store = app.store(STORE_FILE).map_err(|e| e.to_string())?;
+    let value = serde_json::to_value(value).map_err(|e| e.to_string())?;
+    store.set(key, value);
+    Ok(())
+}
+
+/// Remove a value from the persisted store
+pub fn remove(app: &AppHandle, key: &str) -> Result<(), String> {
+    let store = app.store(STORE_FILE).map_err(|e| e.to_string())?;
+    store.delete(key);
+    Ok(())
+}
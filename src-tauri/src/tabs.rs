@@ -1,19 +1,141 @@
 use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
-use tauri::webview::WebviewBuilder;
-use tauri::{AppHandle, Emitter, LogicalPosition, LogicalSize, Manager, Webview, WebviewUrl};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::webview::{NewWindowResponse, PageLoadEvent, PageLoadPayload, WebviewBuilder};
+use tauri::{AppHandle, Emitter, LogicalPosition, LogicalSize, Manager, Url, Webview, WebviewUrl};
 use uuid::Uuid;
 
+use crate::diagnostics;
+use crate::file_search;
+use crate::layout::{self, Layout};
+use crate::library;
 use crate::pool::{self, WebviewPool};
+use crate::session;
+use crate::window;
 
-pub const TAB_BAR_HEIGHT: f64 = 38.0;
+/// How many closed tabs are remembered for reopening
+const MAX_CLOSED_TABS: usize = 20;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Themes a tab can override the global theme with
+const TAB_THEMES: [&str; 3] = ["light", "dark", "high-contrast"];
+
+/// Run in locked tabs before the page loads: no context menu, and JS dialogs are no-ops
+const LOCKED_TAB_SCRIPT: &str = r#"
+window.addEventListener("contextmenu", (e) => e.preventDefault(), true);
+window.alert = () => {};
+window.confirm = () => false;
+window.prompt = () => null;
+"#;
+
+/// File extensions a tab can render; paper folders are opened regardless
+pub const PAPER_EXTENSIONS: [&str; 2] = ["md", "markdown"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TabInfo {
     pub id: String,
     pub tab_type: String, // "home" | "paper"
     pub paper_path: Option<String>,
     pub title: String,
+    /// Temporary file (e.g. piped from stdin) deleted when the tab closes
+    #[serde(default)]
+    pub ephemeral: bool,
+    /// Per-tab theme override; None follows the global theme
+    #[serde(default)]
+    pub theme: Option<String>,
+    /// Accent color (`#RRGGBB`) for visually grouping tabs
+    #[serde(default)]
+    pub color: Option<String>,
+    /// Whether the tab's page has finished loading its route (see `tab_ready`)
+    #[serde(default)]
+    pub loaded: bool,
+    /// Second paper shown beside this one (see `create_split_tab`)
+    #[serde(default)]
+    pub split: Option<SplitInfo>,
+    /// When the tab was last switched to (ms since the Unix epoch), for `sort_tabs`
+    #[serde(default)]
+    pub last_active_ms: u64,
+    /// Temporary preview tab, closed when another tab is switched to (see `create_peek_tab`)
+    #[serde(default)]
+    pub peek: bool,
+    /// Last scroll position reported by the tab's page (see `report_scroll`)
+    #[serde(default)]
+    pub scroll: Option<ScrollPosition>,
+    /// Read-only tab for shared displays whose webview can't leave the app or open
+    /// devtools (see `create_locked_tab`)
+    #[serde(default)]
+    pub locked: bool,
+    /// The page holds work that isn't saved yet (e.g. a scratch tab), so closing the
+    /// window asks first (see `set_tab_unsaved`)
+    #[serde(default)]
+    pub unsaved: bool,
+}
+
+/// How far a tab's page is scrolled, in CSS pixels
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScrollPosition {
+    pub scroll_y: f64,
+    /// How far the page can scroll (content minus viewport height)
+    pub page_height: f64,
+}
+
+impl ScrollPosition {
+    /// Percentage of the page scrolled past, 0-100; a page that fits the
+    /// viewport counts as fully read
+    fn percent(self) -> u8 {
+        if self.page_height <= 0.0 {
+            return 100;
+        }
+        (self.scroll_y / self.page_height * 100.0)
+            .clamp(0.0, 100.0)
+            .round() as u8
+    }
+}
+
+/// The right half of a tab showing two papers; the tab's own webview is the left half
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SplitInfo {
+    /// Label of the right-hand webview
+    pub right_label: String,
+    pub right_type: String,
+    pub right_path: String,
+}
+
+impl TabInfo {
+    /// Describe a tab to create; the id is assigned when its webview is created
+    pub fn new(tab_type: &str, paper_path: Option<String>, title: String) -> Self {
+        Self {
+            id: String::new(),
+            tab_type: tab_type.to_string(),
+            paper_path,
+            title,
+            ..Default::default()
+        }
+    }
+
+    /// Point the tab at a moved paper, returning the (webview label, route) pairs that changed
+    fn rename_path(&mut self, old: &str, new: &str) -> Vec<(String, String)> {
+        let mut rerouted = Vec::new();
+        if let Some(moved) = self
+            .paper_path
+            .as_deref()
+            .and_then(|p| library::moved_path(p, old, new))
+        {
+            rerouted.push((self.id.clone(), get_tab_route(&self.tab_type, Some(&moved))));
+            self.paper_path = Some(moved);
+        }
+        if let Some(split) = &mut self.split {
+            if let Some(moved) = library::moved_path(&split.right_path, old, new) {
+                let route = get_tab_route(&split.right_type, Some(&moved));
+                rerouted.push((split.right_label.clone(), route));
+                split.right_path = moved;
+            }
+        }
+        rerouted
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,8 +144,113 @@ pub struct TabState {
     pub active_tab_id: String,
 }
 
+/// How far a tab has been read, None for home tabs and pages that haven't reported
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TabProgress {
+    pub id: String,
+    pub percent: Option<u8>,
+}
+
+/// The tabs either side of the active one, for prev/next previews
+#[derive(Debug, Clone, Serialize)]
+pub struct AdjacentTabs {
+    pub prev: Option<TabInfo>,
+    pub next: Option<TabInfo>,
+}
+
+impl TabState {
+    /// Whether any tab shows something other than the home library
+    pub fn has_papers(&self) -> bool {
+        self.tabs.iter().any(|t| t.tab_type != "home")
+    }
+
+    /// The paper tab after (or before) the active tab, wrapping around and skipping
+    /// home tabs. None if fewer than two tabs show papers.
+    fn adjacent_paper_tab(&self, forward: bool) -> Option<&str> {
+        let papers: Vec<usize> = (0..self.tabs.len())
+            .filter(|&i| matches!(self.tabs[i].tab_type.as_str(), "paper" | "markdown"))
+            .collect();
+        if papers.len() < 2 {
+            return None;
+        }
+
+        let current = self.tabs.iter().position(|t| t.id == self.active_tab_id);
+        let index = match (current, forward) {
+            (Some(current), true) => papers.iter().find(|&&i| i > current).or(papers.first()),
+            (Some(current), false) => papers
+                .iter()
+                .rev()
+                .find(|&&i| i < current)
+                .or(papers.last()),
+            (None, true) => papers.first(),
+            (None, false) => papers.last(),
+        };
+        index.map(|&i| self.tabs[i].id.as_str())
+    }
+
+    /// Reading progress of every tab, in tab order
+    fn reading_progress(&self) -> Vec<TabProgress> {
+        self.tabs
+            .iter()
+            .map(|t| TabProgress {
+                id: t.id.clone(),
+                percent: t
+                    .scroll
+                    .filter(|_| t.tab_type != "home")
+                    .map(ScrollPosition::percent),
+            })
+            .collect()
+    }
+
+    /// The tabs `prev_tab` and `next_tab` would switch to, wrapping around.
+    /// Both are None with fewer than two tabs.
+    fn adjacent_tabs(&self) -> AdjacentTabs {
+        let len = self.tabs.len();
+        if len <= 1 {
+            return AdjacentTabs {
+                prev: None,
+                next: None,
+            };
+        }
+
+        let current = self
+            .tabs
+            .iter()
+            .position(|t| t.id == self.active_tab_id)
+            .unwrap_or(0);
+        AdjacentTabs {
+            prev: Some(self.tabs[(current + len - 1) % len].clone()),
+            next: Some(self.tabs[(current + 1) % len].clone()),
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// Sort tabs by `"title"` (case-insensitive) or `"recent"` (most recently active
+/// first). Home tabs stay at the front in their current order.
+fn sort_tab_list(tabs: &mut [TabInfo], by: &str) -> Result<(), String> {
+    // Home tabs all get None, which sorts first; the sorts are stable so they keep
+    // their relative order
+    let is_paper = |t: &TabInfo| t.tab_type != "home";
+    match by {
+        "title" => tabs.sort_by_cached_key(|t| is_paper(t).then(|| t.title.to_lowercase())),
+        "recent" => tabs.sort_by_key(|t| is_paper(t).then_some(Reverse(t.last_active_ms))),
+        _ => return Err(format!("Unknown tab sort: {}", by)),
+    }
+    Ok(())
+}
+
 pub struct TabManager {
     state: Mutex<TabState>,
+    /// Last `has_papers` value broadcast, for library-empty/populated transitions
+    populated: AtomicBool,
+    /// Recently closed tabs, most recent last
+    closed: Mutex<Vec<TabInfo>>,
 }
 
 #[allow(dead_code)]
@@ -34,6 +261,8 @@ impl TabManager {
                 tabs: Vec::new(),
                 active_tab_id: String::new(),
             }),
+            populated: AtomicBool::new(false),
+            closed: Mutex::new(Vec::new()),
         }
     }
 
@@ -49,6 +278,63 @@ impl TabManager {
     pub fn set_active(&self, id: &str) {
         let mut state = self.state.lock().unwrap();
         state.active_tab_id = id.to_string();
+        if let Some(tab) = state.tabs.iter_mut().find(|t| t.id == id) {
+            tab.last_active_ms = now_ms();
+        }
+    }
+
+    /// Add a tab and make it active in one step, returning the previously active
+    /// tab id (empty if none).
+    ///
+    /// Concurrent creates each get a different previous tab, so hiding the returned
+    /// tab leaves only the last one added visible.
+    pub fn add_active(&self, mut tab: TabInfo) -> String {
+        let mut state = self.state.lock().unwrap();
+        tab.last_active_ms = now_ms();
+        let id = tab.id.clone();
+        state.tabs.push(tab);
+        std::mem::replace(&mut state.active_tab_id, id)
+    }
+
+    /// Move a tab to `index` (clamped to the last slot), returning false if it doesn't exist
+    pub fn move_tab(&self, id: &str, index: usize) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let Some(pos) = state.tabs.iter().position(|t| t.id == id) else {
+            return false;
+        };
+        let tab = state.tabs.remove(pos);
+        let index = index.min(state.tabs.len());
+        state.tabs.insert(index, tab);
+        true
+    }
+
+    /// Move the active tab one slot right (or left), returning false if it's
+    /// already at that end
+    pub fn move_active(&self, forward: bool) -> bool {
+        // Find and move under one lock, so a concurrent switch or close can't move a
+        // different tab than the one that was active
+        let mut state = self.state.lock().unwrap();
+        let Some(pos) = state.tabs.iter().position(|t| t.id == state.active_tab_id) else {
+            return false;
+        };
+        let target = if forward {
+            pos + 1
+        } else {
+            match pos.checked_sub(1) {
+                Some(target) => target,
+                None => return false,
+            }
+        };
+        if target >= state.tabs.len() {
+            return false;
+        }
+        state.tabs.swap(pos, target);
+        true
+    }
+
+    /// Reorder the tabs (see `sort_tab_list`); the active tab is unchanged
+    pub fn sort(&self, by: &str) -> Result<(), String> {
+        sort_tab_list(&mut self.state.lock().unwrap().tabs, by)
     }
 
     pub fn remove_tab(&self, id: &str) -> Option<usize> {
@@ -61,6 +347,27 @@ impl TabManager {
         }
     }
 
+    /// Remove a tab; if it was active, activate the tab now at its index (or the last).
+    ///
+    /// Returns the newly activated tab id, or None if the active tab is unchanged or
+    /// no tabs remain (the active id is then cleared).
+    pub fn close(&self, id: &str) -> Option<String> {
+        let mut state = self.state.lock().unwrap();
+        let pos = state.tabs.iter().position(|t| t.id == id)?;
+        state.tabs.remove(pos);
+        if state.active_tab_id != id {
+            return None;
+        }
+
+        let Some(next) = state.tabs.get(pos).or(state.tabs.last()) else {
+            state.active_tab_id.clear();
+            return None;
+        };
+        let next = next.id.clone();
+        state.active_tab_id = next.clone();
+        Some(next)
+    }
+
     pub fn get_tab(&self, id: &str) -> Option<TabInfo> {
         let state = self.state.lock().unwrap();
         state.tabs.iter().find(|t| t.id == id).cloned()
@@ -79,174 +386,835 @@ impl TabManager {
         let state = self.state.lock().unwrap();
         state.tabs.iter().position(|t| t.id == state.active_tab_id)
     }
+
+    /// Set a tab's title, returning true if it changed
+    pub fn set_title(&self, id: &str, title: String) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match state.tabs.iter_mut().find(|t| t.id == id) {
+            Some(tab) if tab.title != title => {
+                tab.title = title;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Remember a tab's scroll position, returning false if it doesn't exist
+    pub fn set_scroll(&self, id: &str, scroll: ScrollPosition) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match state.tabs.iter_mut().find(|t| t.id == id) {
+            Some(tab) => {
+                tab.scroll = Some(scroll);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Record whether a tab has unsaved work, returning true if that changed
+    pub fn set_unsaved(&self, id: &str, unsaved: bool) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match state.tabs.iter_mut().find(|t| t.id == id) {
+            Some(tab) if tab.unsaved != unsaved => {
+                tab.unsaved = unsaved;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether any open tab has unsaved work
+    pub fn has_unsaved(&self) -> bool {
+        self.state.lock().unwrap().tabs.iter().any(|t| t.unsaved)
+    }
+
+    /// Mark a tab's page as loaded, returning true the first time
+    pub fn mark_loaded(&self, id: &str) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match state.tabs.iter_mut().find(|t| t.id == id) {
+            Some(tab) if !tab.loaded => {
+                tab.loaded = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Mark a tab's page as loading again, returning true if it had loaded
+    pub fn mark_loading(&self, id: &str) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match state.tabs.iter_mut().find(|t| t.id == id) {
+            Some(tab) if tab.loaded => {
+                tab.loaded = false;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Set a tab's theme override, returning false if the tab doesn't exist
+    pub fn set_theme(&self, id: &str, theme: Option<String>) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match state.tabs.iter_mut().find(|t| t.id == id) {
+            Some(tab) => {
+                tab.theme = theme;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Set a tab's accent color, returning false if the tab doesn't exist
+    pub fn set_color(&self, id: &str, color: Option<String>) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match state.tabs.iter_mut().find(|t| t.id == id) {
+            Some(tab) => {
+                tab.color = color;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The peek tab to close when switching to `target`; None if there is none or
+    /// `target` is the peek tab itself
+    pub fn peek_to_close(&self, target: &str) -> Option<TabInfo> {
+        let state = self.state.lock().unwrap();
+        state
+            .tabs
+            .iter()
+            .find(|t| t.peek && t.id != target)
+            .cloned()
+    }
+
+    /// Make a peek tab permanent, returning false if the tab doesn't exist
+    pub fn promote_peek(&self, id: &str) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match state.tabs.iter_mut().find(|t| t.id == id) {
+            Some(tab) => {
+                tab.peek = false;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remember a closed tab, dropping the oldest beyond MAX_CLOSED_TABS.
+    ///
+    /// Peek tabs are only previews, so they're never remembered.
+    pub fn push_closed(&self, tab: TabInfo) {
+        if tab.peek {
+            return;
+        }
+        let mut closed = self.closed.lock().unwrap();
+        closed.push(tab);
+        if closed.len() > MAX_CLOSED_TABS {
+            closed.remove(0);
+        }
+    }
+
+    /// Recently closed tabs, most recent first
+    pub fn get_closed(&self) -> Vec<TabInfo> {
+        self.closed.lock().unwrap().iter().rev().cloned().collect()
+    }
+
+    pub fn take_closed(&self, id: &str) -> Option<TabInfo> {
+        let mut closed = self.closed.lock().unwrap();
+        let pos = closed.iter().position(|t| t.id == id)?;
+        Some(closed.remove(pos))
+    }
+
+    /// Canonical paths of the papers shown in open tabs, including the right half of split tabs
+    pub fn open_paper_paths(&self) -> HashSet<PathBuf> {
+        let state = self.state.lock().unwrap();
+        state
+            .tabs
+            .iter()
+            .flat_map(|t| {
+                let right = t.split.as_ref().map(|s| &s.right_path);
+                t.paper_path.iter().chain(right)
+            })
+            .map(|path| file_search::canonical_path(path))
+            .collect()
+    }
+
+    pub fn is_paper_open(&self, path: &str) -> bool {
+        self.open_paper_paths()
+            .contains(&file_search::canonical_path(path))
+    }
+
+    /// The open tab showing the paper (in either half of a split), comparing canonical
+    /// paths; the active tab is preferred, then the first in tab order
+    pub fn tab_showing(&self, path: &str) -> Option<String> {
+        let path = file_search::canonical_path(path);
+        let state = self.state.lock().unwrap();
+        let shows = |t: &&TabInfo| {
+            let right = t.split.as_ref().map(|s| &s.right_path);
+            t.paper_path
+                .iter()
+                .chain(right)
+                .any(|p| file_search::canonical_path(p) == path)
+        };
+        let active = state.tabs.iter().find(|t| t.id == state.active_tab_id);
+        active
+            .filter(shows)
+            .or_else(|| state.tabs.iter().find(shows))
+            .map(|t| t.id.clone())
+    }
+
+    /// Ids of open tabs showing a paper at or inside `path`
+    pub fn tabs_showing(&self, path: &str) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        let inside = |p: &str| std::path::Path::new(p).starts_with(path);
+        state
+            .tabs
+            .iter()
+            .filter(|t| {
+                t.paper_path.as_deref().is_some_and(inside)
+                    || t.split.as_ref().is_some_and(|s| inside(&s.right_path))
+            })
+            .map(|t| t.id.clone())
+            .collect()
+    }
+
+    /// Paper tabs whose paper is at or inside `dir`, comparing canonical paths.
+    /// Split halves aren't considered.
+    pub fn tabs_under(&self, dir: &str) -> Vec<TabInfo> {
+        let dir = file_search::canonical_path(dir);
+        let state = self.state.lock().unwrap();
+        state
+            .tabs
+            .iter()
+            .filter(|t| {
+                t.paper_path
+                    .as_deref()
+                    .is_some_and(|p| file_search::canonical_path(p).starts_with(&dir))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Point open and closed tabs at a moved paper.
+    ///
+    /// Returns the (webview label, route) pairs of the open webviews still showing the
+    /// old path, for `reroute_webviews`.
+    pub fn rename_path(&self, old: &str, new: &str) -> Vec<(String, String)> {
+        for tab in self.closed.lock().unwrap().iter_mut() {
+            tab.rename_path(old, new);
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state
+            .tabs
+            .iter_mut()
+            .flat_map(|tab| tab.rename_path(old, new))
+            .collect()
+    }
 }
 
-fn emit_tab_state(app: &AppHandle) {
+pub(crate) fn emit_tab_state(app: &AppHandle) {
     let manager = app.state::<TabManager>();
     let state = manager.get_state();
+
+    // Signal when the window gains its first paper tab or drops back to home only
+    let populated = state.has_papers();
+    if manager.populated.swap(populated, Ordering::Relaxed) != populated {
+        let event = if populated {
+            "library-populated"
+        } else {
+            "library-empty"
+        };
+        let _ = app.emit(event, ());
+    }
+
+    window::sync_title(app, &state);
     let _ = app.emit("tab-state-changed", state);
+    session::autosave(app);
 }
 
-fn get_webview_url(tab_type: &str, paper_path: Option<&str>) -> WebviewUrl {
+/// Route a tab webview loads, e.g. `/tab?type=markdown&path=%2Fp%2Fa%20b.md`.
+///
+/// Each value is percent-encoded as a single component (`&`, `#`, `%`, `+`, `=` and
+/// non-ASCII included), so a path can never end the query or spill into another
+/// param. The page reads them back with `URLSearchParams.get` (`+` as a space, then
+/// percent-decoding).
+pub(crate) fn get_tab_route(tab_type: &str, paper_path: Option<&str>) -> String {
     let mut url = String::from("/tab?type=");
-    url.push_str(tab_type);
+    url.push_str(&urlencoding::encode(tab_type));
     if let Some(path) = paper_path {
         url.push_str("&path=");
         url.push_str(&urlencoding::encode(path));
     }
-    WebviewUrl::App(url.into())
+    url
+}
+
+fn get_webview_url(tab_type: &str, paper_path: Option<&str>) -> WebviewUrl {
+    WebviewUrl::App(get_tab_route(tab_type, paper_path).into())
 }
 
 /// Configure a pooled webview by calling __setTabParams via eval.
+///
+/// Both values are percent-encoded like in `get_tab_route` (which also keeps them
+/// safe inside the script's string literals); the page decodes them with
+/// `decodeURIComponent`. With a `route`, the page also pushes it onto its history
+/// and acknowledges with `navigate_ack`.
 fn configure_pooled_webview(
     webview: &Webview,
     tab_type: &str,
     paper_path: Option<&str>,
+    route: Option<&str>,
 ) -> Result<(), String> {
-    let encoded_path = paper_path
-        .map(|p| urlencoding::encode(p).to_string())
-        .map(|s| format!("\"{}\"", s))
-        .unwrap_or_else(|| "null".to_string());
+    let quoted = |s: Option<String>| s.map_or_else(|| "null".to_string(), |s| format!("\"{}\"", s));
+    let encoded_path = quoted(paper_path.map(|p| urlencoding::encode(p).to_string()));
+    // Routes from get_tab_route are already percent-encoded
+    let route = quoted(route.map(str::to_string));
 
     let script = format!(
-        "if (window.__setTabParams) {{ window.__setTabParams(\"{}\", {}); }}",
-        tab_type, encoded_path
+        "if (window.__setTabParams) {{ window.__setTabParams(\"{}\", {}, {}); }}",
+        urlencoding::encode(tab_type),
+        encoded_path,
+        route
     );
 
     webview.eval(&script).map_err(|e| e.to_string())
 }
 
-/// Create a tab using the pool if available, otherwise fall back to fresh creation.
-fn create_tab_with_pool(
+/// Point a claimed pool webview at a tab's content.
+///
+/// With `navigate_in_place` set, `__setTabParams` is also given the route, so the
+/// page switches route through its router without reloading the bundle. If it
+/// doesn't call `navigate_ack` within NAVIGATE_ACK_TIMEOUT, the route is loaded in
+/// full.
+fn route_claimed_webview(
     app: &AppHandle,
+    webview: &Webview,
     tab_type: &str,
-    paper_path: Option<String>,
-    title: String,
-) -> Result<String, String> {
+    paper_path: Option<&str>,
+) -> Result<(), String> {
     let pool = app.state::<WebviewPool>();
-    let manager = app.state::<TabManager>();
+    if !pool.navigate_in_place() {
+        return configure_pooled_webview(webview, tab_type, paper_path, None);
+    }
 
-    // Get the main window
-    let window = app.get_window("main").ok_or("Main window not found")?;
+    let label = webview.label().to_string();
+    let route = get_tab_route(tab_type, paper_path);
+    pool.expect_ack(&label);
+    configure_pooled_webview(webview, tab_type, paper_path, Some(&route))?;
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(pool::NAVIGATE_ACK_TIMEOUT);
+        if !app.state::<WebviewPool>().take_ack(&label) {
+            return;
+        }
+        let message = format!("{} didn't acknowledge navigation, reloading it", label);
+        diagnostics::record(&app, log::Level::Warn, "pool", message);
+        let Some(webview) = app.get_webview(&label) else {
+            return;
+        };
+        let url = webview.url().ok().and_then(|url| url.join(&route).ok());
+        if let Some(url) = url {
+            if let Err(e) = webview.navigate(url) {
+                log::error!("Failed to navigate {}: {}", label, e);
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Create a tab using the pool if available, otherwise fall back to fresh creation.
+pub(crate) fn create_tab_with_pool(app: &AppHandle, tab: TabInfo) -> Result<String, String> {
+    create_tab_internal(app, TabRequest::pooled(tab))
+}
+
+/// Where a new tab's webview comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WebviewSource {
+    /// Claim a pooled webview, falling back to a fresh one if the pool is empty
+    Pool,
+    Fresh,
+}
+
+/// A tab to create and how; every entry point builds one for `create_tab_internal`
+struct TabRequest {
+    tab: TabInfo,
+    use_pool: bool,
+    /// Skip the file type check
+    force: bool,
+}
+
+impl TabRequest {
+    fn pooled(tab: TabInfo) -> Self {
+        Self {
+            tab,
+            use_pool: true,
+            force: false,
+        }
+    }
 
-    // Get window dimensions for positioning
-    let size = window.inner_size().map_err(|e| e.to_string())?;
-    let scale = window.scale_factor().unwrap_or(1.0);
+    fn fresh(tab: TabInfo) -> Self {
+        Self {
+            use_pool: false,
+            ..Self::pooled(tab)
+        }
+    }
 
-    let width = size.width as f64 / scale;
-    let height = (size.height as f64 / scale) - TAB_BAR_HEIGHT;
+    /// Validate the tab and decide where its webview comes from.
+    ///
+    /// Locked tabs need a webview built for them, so they never use the pool.
+    fn plan(&self) -> Result<WebviewSource, String> {
+        validate_tab(&self.tab, self.force)?;
+        Ok(if self.use_pool && !self.tab.locked {
+            WebviewSource::Pool
+        } else {
+            WebviewSource::Fresh
+        })
+    }
+}
 
-    // Hide currently active tab's webview if it exists
-    let current_active = manager.get_state().active_tab_id;
-    if !current_active.is_empty() {
-        if let Some(current_webview) = app.get_webview(&current_active) {
-            let _ = current_webview.hide();
+/// Check a tab's theme and, unless `force` is set, that its file is one a tab can render.
+///
+/// Home tabs and paper folders are exempt from the file type check.
+fn validate_tab(tab: &TabInfo, force: bool) -> Result<(), String> {
+    validate_theme(tab.theme.as_deref())?;
+    // Guard drag-drop and CLI opens against rendering images or binaries as a paper
+    if tab.tab_type != "home" && !force {
+        if let Some(path) = &tab.paper_path {
+            if !std::path::Path::new(path).is_dir() && !is_supported_file(path) {
+                return Err("unsupported file type".to_string());
+            }
         }
     }
+    Ok(())
+}
+
+/// The one path every tab is created through: plans the request (see
+/// `TabRequest::plan`), claims a pooled webview or creates a fresh one, activates the
+/// tab and returns its id.
+fn create_tab_internal(app: &AppHandle, request: TabRequest) -> Result<String, String> {
+    let source = request.plan()?;
+    let mut tab = request.tab;
+    let pool = app.state::<WebviewPool>();
+    let manager = app.state::<TabManager>();
+
+    // Get the main window
+    let window = app.get_window("main").ok_or("Main window not found")?;
+
+    // Get content area for positioning
+    let (position, size) = layout::content_bounds(app, &window)?;
+
+    // Leave split view so the new tab isn't shown beside a stale half
+    layout::end_split(app);
 
     let tab_id: String;
 
-    // Try to claim from pool
-    if let Some(pool_label) = pool.claim() {
-        log::info!("Claimed webview from pool: {}", pool_label);
+    let claimed = match source {
+        WebviewSource::Pool => pool.claim_for(&tab.tab_type, tab.paper_path.as_deref()),
+        WebviewSource::Fresh => None,
+    };
+    if let Some(claimed) = claimed {
+        let pool_label = claimed.label;
+        let message = format!("Claimed webview from pool: {}", pool_label);
+        diagnostics::record(app, log::Level::Info, "pool", message);
 
         if let Some(webview) = app.get_webview(&pool_label) {
-            // Configure the pooled webview for the requested content
-            configure_pooled_webview(&webview, tab_type, paper_path.as_deref())?;
+            // Configure the pooled webview for the requested content, unless it
+            // was prefetched to it (see `prefetch_paper`)
+            if !claimed.prefetched {
+                route_claimed_webview(app, &webview, &tab.tab_type, tab.paper_path.as_deref())?;
+            }
 
             // Show and focus the webview
             let _ = webview.show();
             let _ = webview.set_focus();
 
+            // Pool webviews have already loaded the home view
+            tab.loaded = if claimed.prefetched {
+                claimed.ready
+            } else {
+                tab.tab_type == "home"
+            };
             tab_id = pool_label;
         } else {
             // Pool webview not found, create fresh
-            log::warn!("Pool webview not found, creating fresh");
-            tab_id = create_fresh_webview(app, tab_type, paper_path.as_deref(), &window, width, height)?;
+            let message = "Pool webview not found, creating fresh".to_string();
+            diagnostics::record(app, log::Level::Warn, "pool", message);
+            tab_id = create_fresh_webview(
+                app,
+                &tab.tab_type,
+                tab.paper_path.as_deref(),
+                tab.locked,
+                &window,
+                position,
+                size,
+            )?;
         }
 
         // Trigger pool replenishment asynchronously
         pool::replenish_pool(app.clone());
     } else {
         // Pool empty, create fresh webview
-        log::info!("Pool empty, creating fresh webview");
-        tab_id = create_fresh_webview(app, tab_type, paper_path.as_deref(), &window, width, height)?;
+        let message = "Pool empty, creating fresh webview".to_string();
+        diagnostics::record(app, log::Level::Info, "pool", message);
+        tab_id = create_fresh_webview(
+            app,
+            &tab.tab_type,
+            tab.paper_path.as_deref(),
+            tab.locked,
+            &window,
+            position,
+            size,
+        )?;
+    }
+
+    // Every caller opens a paper the user asked for; session restores go through
+    // `replace_tabs` instead, so they don't reorder recents
+    if tab.tab_type != "home" && !tab.ephemeral {
+        if let Some(path) = &tab.paper_path {
+            library::record_recent(app, path);
+        }
     }
 
-    // Add tab to state
-    let tab_info = TabInfo {
-        id: tab_id.clone(),
-        tab_type: tab_type.to_string(),
-        paper_path,
-        title,
+    let message = match &tab.paper_path {
+        Some(path) => format!("Opened {} tab {}: {}", tab.tab_type, tab_id, path),
+        None => format!("Opened {} tab {}", tab.tab_type, tab_id),
     };
-    manager.add_tab(tab_info);
-    manager.set_active(&tab_id);
+    diagnostics::record(app, log::Level::Info, "tab", message);
 
+    tab.id = tab_id.clone();
+    activate_new_tab(&manager, tab, |previous| {
+        set_tab_visible(app, previous, false)
+    });
+    // Opening another tab moves on from the peek tab just like switching does
+    close_peek(app, &tab_id);
+
+    relayout_if_single_tab_bar_hidden(app);
     emit_tab_state(app);
     Ok(tab_id)
 }
 
+/// Add a just-shown tab as the active one, then `hide` the tab it replaced.
+///
+/// The previous tab is only read here, not when creation started, so a concurrent
+/// create can't also hide the same tab and leave both new webviews showing.
+fn activate_new_tab(manager: &TabManager, tab: TabInfo, hide: impl FnOnce(&str)) {
+    let previous = manager.add_active(tab);
+    if !previous.is_empty() {
+        hide(&previous);
+    }
+}
+
+/// Show (and focus) or hide a tab's webviews, including the right half of a split tab
+pub(crate) fn set_tab_visible(app: &AppHandle, id: &str, visible: bool) {
+    let right = app
+        .state::<TabManager>()
+        .get_tab(id)
+        .and_then(|t| t.split)
+        .map(|s| s.right_label);
+
+    // The left half goes last so it ends up focused
+    for label in right.iter().map(String::as_str).chain([id]) {
+        if let Some(webview) = app.get_webview(label) {
+            if visible {
+                let _ = webview.show();
+                let _ = webview.set_focus();
+            } else {
+                let _ = webview.hide();
+            }
+        }
+    }
+}
+
+/// Destroy a tab's webviews; hiding alone would leave them alive in the window
+fn close_tab_webviews(app: &AppHandle, tab: &TabInfo) {
+    let right = tab.split.as_ref().map(|s| s.right_label.as_str());
+    for label in [tab.id.as_str()].into_iter().chain(right) {
+        if let Some(webview) = app.get_webview(label) {
+            let _ = webview.close();
+        }
+    }
+}
+
+/// Re-layout webviews after the tab count changes, in case the tab bar appeared or hid.
+fn relayout_if_single_tab_bar_hidden(app: &AppHandle) {
+    if app.state::<Layout>().hide_single_tab_bar() {
+        layout::relayout(app);
+    }
+}
+
 /// Create a fresh webview (non-pooled).
 fn create_fresh_webview(
     _app: &AppHandle,
     tab_type: &str,
     paper_path: Option<&str>,
+    locked: bool,
     window: &tauri::Window,
-    width: f64,
-    height: f64,
+    position: LogicalPosition<f64>,
+    size: LogicalSize<f64>,
 ) -> Result<String, String> {
     let tab_id = format!("tab-{}", Uuid::new_v4());
+    add_tab_webview(
+        window, &tab_id, tab_type, paper_path, locked, position, size,
+    )?;
+    Ok(tab_id)
+}
 
-    let url = get_webview_url(tab_type, paper_path);
-    let webview_builder = WebviewBuilder::new(&tab_id, url);
+/// Whether a URL is the app's own frontend (bundled, or the dev server in `tauri dev`)
+fn is_app_url(url: &Url, dev: bool) -> bool {
+    match url.scheme() {
+        "tauri" => true,
+        "http" | "https" => match url.host_str() {
+            Some("tauri.localhost") => true,
+            Some("localhost") => dev,
+            _ => false,
+        },
+        _ => false,
+    }
+}
 
-    let position = LogicalPosition::new(0.0, TAB_BAR_HEIGHT);
-    let webview_size = LogicalSize::new(width, height);
+fn add_tab_webview(
+    window: &tauri::Window,
+    label: &str,
+    tab_type: &str,
+    paper_path: Option<&str>,
+    locked: bool,
+    position: LogicalPosition<f64>,
+    size: LogicalSize<f64>,
+) -> Result<(), String> {
+    let url = get_webview_url(tab_type, paper_path);
+    let mut webview_builder = WebviewBuilder::new(label, url).on_page_load(on_page_load);
+    if locked {
+        webview_builder = webview_builder
+            .devtools(false)
+            .initialization_script(LOCKED_TAB_SCRIPT)
+            .on_navigation(|url| is_app_url(url, tauri::is_dev()))
+            .on_new_window(|_, _| NewWindowResponse::Deny);
+    }
 
     let webview = window
-        .add_child(webview_builder, position, webview_size)
+        .add_child(webview_builder, position, size)
         .map_err(|e| e.to_string())?;
 
     let _ = webview.set_focus();
 
-    Ok(tab_id)
+    Ok(())
 }
 
-/// Create the initial tab at startup (before pool is initialized).
-pub fn create_initial_tab(app: &AppHandle) -> Result<(), String> {
-    let manager = app.state::<TabManager>();
+/// Create any missing webviews of a tab: tabs added by `replace_tabs` have none
+/// and a split tab's right half is created after the tab itself.
+pub(crate) fn ensure_webview(app: &AppHandle, id: &str) -> Result<(), String> {
+    let tab = app
+        .state::<TabManager>()
+        .get_tab(id)
+        .ok_or("Tab not found")?;
+
+    let mut missing = Vec::new();
+    if app.get_webview(id).is_none() {
+        missing.push((id, tab.tab_type.as_str(), tab.paper_path.as_deref()));
+    }
+    if let Some(split) = &tab.split {
+        if app.get_webview(&split.right_label).is_none() {
+            missing.push((
+                split.right_label.as_str(),
+                split.right_type.as_str(),
+                Some(split.right_path.as_str()),
+            ));
+        }
+    }
+    if missing.is_empty() {
+        return Ok(());
+    }
+
     let window = app.get_window("main").ok_or("Main window not found")?;
+    let (position, size) = layout::content_bounds(app, &window)?;
+    // The right half of a locked tab is locked as well
+    for (label, tab_type, paper_path) in missing {
+        add_tab_webview(
+            &window, label, tab_type, paper_path, tab.locked, position, size,
+        )?;
+    }
+    if tab.split.is_some() {
+        layout::relayout(app);
+    }
+    Ok(())
+}
 
-    let size = window.inner_size().map_err(|e| e.to_string())?;
-    let scale = window.scale_factor().unwrap_or(1.0);
+/// Replace every open tab with `tabs`, activating the one at `active`.
+///
+/// Only the active tab's webview is created up front; the rest load the first time
+/// they're switched to, so restoring a large session doesn't spike memory. Recents
+/// and their open times are left alone, since reopening a session isn't the user
+/// opening its papers.
+pub(crate) fn replace_tabs(
+    app: &AppHandle,
+    tabs: Vec<TabInfo>,
+    active: usize,
+) -> Result<(), String> {
+    if tabs.is_empty() {
+        return Err("No tabs to open".to_string());
+    }
 
-    let width = size.width as f64 / scale;
-    let height = (size.height as f64 / scale) - TAB_BAR_HEIGHT;
+    let manager = app.state::<TabManager>();
+    layout::end_split(app);
+    for tab in manager.get_state().tabs {
+        if tab.ephemeral {
+            remove_ephemeral_file(&tab);
+        }
+        close_tab_webviews(app, &tab);
+        manager.remove_tab(&tab.id);
+    }
 
-    let tab_id = create_fresh_webview(app, "home", None, &window, width, height)?;
+    let active = active.min(tabs.len() - 1);
+    let mut active_id = String::new();
+    for (i, mut tab) in tabs.into_iter().enumerate() {
+        tab.id = format!("tab-{}", Uuid::new_v4());
+        tab.loaded = false;
+        tab.unsaved = false;
+        // Sessions saved before peek tabs were left out may still mark one
+        tab.peek = false;
+        if let Some(split) = &mut tab.split {
+            split.right_label = format!("tab-{}", Uuid::new_v4());
+        }
+        if i == active {
+            active_id = tab.id.clone();
+        }
+        manager.add_tab(tab);
+    }
 
-    let tab_info = TabInfo {
-        id: tab_id.clone(),
-        tab_type: "home".to_string(),
-        paper_path: None,
-        title: "Library".to_string(),
-    };
-    manager.add_tab(tab_info);
-    manager.set_active(&tab_id);
+    manager.set_active(&active_id);
+    ensure_webview(app, &active_id)?;
+    set_tab_visible(app, &active_id, true);
 
+    relayout_if_single_tab_bar_hidden(app);
     emit_tab_state(app);
     Ok(())
 }
 
-#[tauri::command]
+/// Create the initial tab at startup (before pool is initialized).
+pub fn create_initial_tab(app: &AppHandle) -> Result<(), String> {
+    create_tab_internal(app, initial_tab_request())?;
+    Ok(())
+}
+
+/// The pool is filled after the initial tab, so there's nothing to claim yet
+fn initial_tab_request() -> TabRequest {
+    TabRequest::fresh(home_tab())
+}
+
+/// Describe a new home (library) tab
+fn home_tab() -> TabInfo {
+    TabInfo::new("home", None, "Library".to_string())
+}
+
+#[tauri::command]
 pub fn create_tab(
     app: AppHandle,
     tab_type: String,
     paper_path: Option<String>,
     title: String,
+    theme: Option<String>,
+    force: Option<bool>,
+    from_query: Option<String>,
 ) -> Result<String, String> {
-    create_tab_with_pool(&app, &tab_type, paper_path, title)
+    let request = create_tab_request(&tab_type, paper_path, title, theme, force);
+    let opened_path = request.tab.paper_path.clone();
+    let id = create_tab_internal(&app, request)?;
+
+    // The search that led here, for suggestions and the local search-open log
+    if let (Some(query), Some(path)) = (from_query, opened_path) {
+        file_search::save_last_query(&app, &query);
+        library::record_search_query(&app, &query);
+        library::record_search_open(&app, &query, &path);
+    }
+    Ok(id)
+}
+
+/// What the `create_tab` command asks for
+fn create_tab_request(
+    tab_type: &str,
+    paper_path: Option<String>,
+    title: String,
+    theme: Option<String>,
+    force: Option<bool>,
+) -> TabRequest {
+    let tab = TabInfo {
+        theme,
+        ..TabInfo::new(tab_type, paper_path, title)
+    };
+    TabRequest {
+        force: force.unwrap_or(false),
+        ..TabRequest::pooled(tab)
+    }
+}
+
+/// Whether a file's extension is one a tab can render.
+///
+/// The single check for what counts as a paper file: the indexer, the clipboard and
+/// Finder handoffs go through it too.
+pub fn is_supported_file(path: impl AsRef<std::path::Path>) -> bool {
+    path.as_ref()
+        .extension()
+        .is_some_and(|ext| PAPER_EXTENSIONS.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+}
+
+fn validate_theme(theme: Option<&str>) -> Result<(), String> {
+    match theme {
+        Some(theme) if !TAB_THEMES.contains(&theme) => Err(format!("Unknown theme: {}", theme)),
+        _ => Ok(()),
+    }
+}
+
+/// Override the theme of a single tab (None returns it to the global theme).
+///
+/// The tab's own webview receives `tab-theme-changed` so it can restyle itself.
+#[tauri::command]
+pub fn set_tab_theme(app: AppHandle, id: String, theme: Option<String>) -> Result<(), String> {
+    validate_theme(theme.as_deref())?;
+    if !app.state::<TabManager>().set_theme(&id, theme.clone()) {
+        return Err("Tab not found".to_string());
+    }
+
+    let _ = app.emit_to(id.as_str(), "tab-theme-changed", theme);
+    emit_tab_state(&app);
+    Ok(())
+}
+
+/// Remove a tab and destroy its webviews without emitting state.
+///
+/// Returns the newly activated tab id if the tab was active (see `TabManager::close`).
+fn discard_tab(app: &AppHandle, tab: &TabInfo) -> Option<String> {
+    let manager = app.state::<TabManager>();
+    if tab.ephemeral {
+        remove_ephemeral_file(tab);
+    } else if tab.tab_type != "home" {
+        manager.push_closed(tab.clone());
+    }
+    if app
+        .state::<Layout>()
+        .split()
+        .is_some_and(|split| split.contains(&tab.id))
+    {
+        layout::end_split(app);
+    }
+    let new_active = manager.close(&tab.id);
+    close_tab_webviews(app, tab);
+    let message = format!("Closed tab {}", tab.id);
+    diagnostics::record(app, log::Level::Info, "tab", message);
+    new_active
+}
+
+/// Show the tab that replaced a closed active tab, then publish the new state
+fn finish_close(app: &AppHandle, new_active: Option<String>) {
+    if let Some(new_id) = new_active {
+        if let Err(e) = ensure_webview(app, &new_id) {
+            let message = format!("Failed to load tab {}: {}", new_id, e);
+            diagnostics::record(app, log::Level::Error, "tab", message);
+        }
+        set_tab_visible(app, &new_id, true);
+    }
+
+    relayout_if_single_tab_bar_hidden(app);
+    emit_tab_state(app);
 }
 
 #[tauri::command]
@@ -259,42 +1227,73 @@ pub fn close_tab(app: AppHandle, id: String) -> Result<(), String> {
         return Ok(());
     }
 
-    let was_active = state.active_tab_id == id;
-    let closed_index = manager.remove_tab(&id);
+    // A repeated close (e.g. middle click and shortcut together) is a no-op
+    let Some(tab) = state.tabs.iter().find(|t| t.id == id) else {
+        return Ok(());
+    };
+    let new_active = discard_tab(&app, tab);
+    finish_close(&app, new_active);
+    Ok(())
+}
+
+/// Clamp `[start, end)` to `len` tabs, leaving at least one tab open
+fn close_range(start: usize, end: usize, len: usize) -> std::ops::Range<usize> {
+    let end = end.min(len);
+    let start = start.min(end);
+    if end - start == len {
+        start..end.saturating_sub(1)
+    } else {
+        start..end
+    }
+}
+
+/// Close the tabs at indices `[start, end)`, clamped to the open tabs, with a single
+/// state update. The last tab is never closed.
+///
+/// If the active tab is in the range, the first tab after it (or the one before) is activated.
+#[tauri::command]
+pub fn close_tab_range(app: AppHandle, start: usize, end: usize) -> Result<(), String> {
+    let state = app.state::<TabManager>().get_state();
+    let range = close_range(start, end, state.tabs.len());
+    if range.is_empty() {
+        return Ok(());
+    }
 
-    // Destroy the webview
-    if let Some(webview) = app.get_webview(&id) {
-        // Close/destroy the webview
-        // Note: In Tauri 2, we may need to use a different approach
-        // For now, hiding it - actual cleanup happens when the webview is dropped
-        let _ = webview.hide();
-        // The webview will be cleaned up when all references are dropped
-    }
-
-    // If this was the active tab, switch to another
-    if was_active {
-        if let Some(idx) = closed_index {
-            let new_state = manager.get_state();
-            let new_index = if idx >= new_state.tabs.len() {
-                new_state.tabs.len().saturating_sub(1)
-            } else {
-                idx
-            };
-            if let Some(new_tab) = new_state.tabs.get(new_index) {
-                let new_id = new_tab.id.clone();
-                manager.set_active(&new_id);
-                if let Some(webview) = app.get_webview(&new_id) {
-                    let _ = webview.show();
-                    let _ = webview.set_focus();
-                }
-            }
+    let mut new_active = None;
+    for tab in &state.tabs[range] {
+        if let Some(id) = discard_tab(&app, tab) {
+            new_active = Some(id);
         }
     }
-
-    emit_tab_state(&app);
+    finish_close(&app, new_active);
     Ok(())
 }
 
+/// Close every paper tab showing a paper inside `dir_prefix` with a single state
+/// update, returning how many were closed. The last tab is never closed.
+///
+/// If the active tab is closed, the tab now at its position (or the last) is activated.
+#[tauri::command]
+pub fn close_tabs_under(app: AppHandle, dir_prefix: String) -> Result<usize, String> {
+    let manager = app.state::<TabManager>();
+    let mut under = manager.tabs_under(&dir_prefix);
+    if under.len() == manager.tab_count() {
+        under.pop();
+    }
+    if under.is_empty() {
+        return Ok(0);
+    }
+
+    let mut new_active = None;
+    for tab in &under {
+        if let Some(id) = discard_tab(&app, tab) {
+            new_active = Some(id);
+        }
+    }
+    finish_close(&app, new_active);
+    Ok(under.len())
+}
+
 #[tauri::command]
 pub fn switch_tab(app: AppHandle, id: String) -> Result<(), String> {
     let manager = app.state::<TabManager>();
@@ -304,25 +1303,58 @@ pub fn switch_tab(app: AppHandle, id: String) -> Result<(), String> {
     if !state.tabs.iter().any(|t| t.id == id) {
         return Err("Tab not found".to_string());
     }
-
-    // Hide current active webview
-    if !state.active_tab_id.is_empty() && state.active_tab_id != id {
-        if let Some(current_webview) = app.get_webview(&state.active_tab_id) {
-            let _ = current_webview.hide();
+    ensure_webview(&app, &id)?;
+
+    // Switching to a tab outside the split leaves split view
+    let in_split = match app.state::<Layout>().split() {
+        Some(split) if split.contains(&id) => true,
+        Some(_) => {
+            layout::end_split(&app);
+            false
         }
+        None => false,
+    };
+
+    // Hide current active webview (unless it's the other half of the split)
+    if !in_split && !state.active_tab_id.is_empty() && state.active_tab_id != id {
+        set_tab_visible(&app, &state.active_tab_id, false);
     }
 
     // Show target webview
-    if let Some(target_webview) = app.get_webview(&id) {
-        let _ = target_webview.show();
-        let _ = target_webview.set_focus();
-    }
+    set_tab_visible(&app, &id, true);
 
     manager.set_active(&id);
+
+    // Moving on from a peek tab closes it
+    if close_peek(&app, &id) {
+        relayout_if_single_tab_bar_hidden(&app);
+    }
     emit_tab_state(&app);
     Ok(())
 }
 
+/// Close the peek tab (if any) when `target` is shown instead, returning true if
+/// one was closed. Called by every path that switches to or opens a tab.
+fn close_peek(app: &AppHandle, target: &str) -> bool {
+    let Some(peek) = app.state::<TabManager>().peek_to_close(target) else {
+        return false;
+    };
+    discard_tab(app, &peek);
+    true
+}
+
+/// Give keyboard focus back to the active tab's webview without switching tabs.
+#[tauri::command]
+pub fn focus_active_webview(app: AppHandle) -> Result<(), String> {
+    let active = app.state::<TabManager>().get_state().active_tab_id;
+    if active.is_empty() {
+        return Err("No active tab".to_string());
+    }
+
+    let webview = app.get_webview(&active).ok_or("Tab webview not found")?;
+    webview.set_focus().map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn next_tab(app: AppHandle) -> Result<(), String> {
     let manager = app.state::<TabManager>();
@@ -367,6 +1399,61 @@ pub fn prev_tab(app: AppHandle) -> Result<(), String> {
     switch_tab(app, prev_id)
 }
 
+/// Switch to the next paper tab, skipping home tabs (no-op with fewer than two papers)
+#[tauri::command]
+pub fn next_paper_tab(app: AppHandle) -> Result<(), String> {
+    let state = app.state::<TabManager>().get_state();
+    match state.adjacent_paper_tab(true) {
+        Some(id) => switch_tab(app, id.to_string()),
+        None => Ok(()),
+    }
+}
+
+/// Switch to the previous paper tab, skipping home tabs (no-op with fewer than two papers)
+#[tauri::command]
+pub fn prev_paper_tab(app: AppHandle) -> Result<(), String> {
+    let state = app.state::<TabManager>().get_state();
+    match state.adjacent_paper_tab(false) {
+        Some(id) => switch_tab(app, id.to_string()),
+        None => Ok(()),
+    }
+}
+
+/// Move the active tab one slot right (or left), broadcasting the new order if it
+/// moved. Shared by `move_tab_left` and `move_tab_right`.
+fn move_active_tab(app: &AppHandle, forward: bool) -> Result<(), String> {
+    if app.state::<TabManager>().move_active(forward) {
+        emit_tab_state(app);
+    }
+    Ok(())
+}
+
+/// Move the active tab one slot left (no-op for the first tab)
+#[tauri::command]
+pub fn move_tab_left(app: AppHandle) -> Result<(), String> {
+    move_active_tab(&app, false)
+}
+
+/// Move the active tab one slot right (no-op for the last tab)
+#[tauri::command]
+pub fn move_tab_right(app: AppHandle) -> Result<(), String> {
+    move_active_tab(&app, true)
+}
+
+/// Reorder the open tabs by `"title"` or `"recent"`, keeping home tabs first
+#[tauri::command]
+pub fn sort_tabs(app: AppHandle, by: String) -> Result<(), String> {
+    app.state::<TabManager>().sort(&by)?;
+    emit_tab_state(&app);
+    Ok(())
+}
+
+/// The tabs before and after the active one, wrapping like `prev_tab`/`next_tab`
+#[tauri::command]
+pub fn get_adjacent_tabs(app: AppHandle) -> AdjacentTabs {
+    app.state::<TabManager>().get_state().adjacent_tabs()
+}
+
 #[tauri::command]
 pub fn switch_tab_by_index(app: AppHandle, index: usize) -> Result<(), String> {
     let manager = app.state::<TabManager>();
@@ -393,17 +1480,400 @@ pub fn update_current_tab_title(
     title: String,
 ) -> Result<(), String> {
     let tab_id = webview.label();
+    // A window from `open_new_window` has no tab; its title goes on the window
+    if tab_id.starts_with(window::EXTRA_WINDOW_PREFIX) {
+        return webview
+            .window()
+            .set_title(&title)
+            .map_err(|e| e.to_string());
+    }
+    let manager = app.state::<TabManager>();
+
+    // Pages that rewrite document.title often would otherwise flood tab-state-changed
+    if manager.set_title(tab_id, title) {
+        emit_tab_state(&app);
+    }
+    Ok(())
+}
+
+/// Whether a string is a `#RRGGBB` hex color
+fn is_hex_color(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Color-code a tab (None clears the color); the frontend renders it in the tab bar.
+#[tauri::command]
+pub fn set_tab_color(app: AppHandle, id: String, color: Option<String>) -> Result<(), String> {
+    if let Some(color) = &color {
+        if !is_hex_color(color) {
+            return Err(format!("Expected a #RRGGBB color, got {}", color));
+        }
+    }
+    if !app.state::<TabManager>().set_color(&id, color) {
+        return Err("Tab not found".to_string());
+    }
+
+    emit_tab_state(&app);
+    Ok(())
+}
+
+/// Mark a tab's page as loaded, emitting `tab-loaded` with its id the first time.
+///
+/// Only `tab_ready` calls this: the document finishing (see `on_page_load`) comes
+/// before the page has fetched and rendered the tab's paper. Returns false if it was
+/// already loaded or the webview isn't a tab's (yet).
+fn mark_tab_loaded(app: &AppHandle, id: &str) -> bool {
+    if !app.state::<TabManager>().mark_loaded(id) {
+        return false;
+    }
+    let _ = app.emit("tab-loaded", id);
+    emit_tab_state(app);
+    true
+}
+
+/// Page load handler for tab and pool webviews: a full navigation starting (such as
+/// a claimed pool webview reloaded at its route) marks the tab loading again, until
+/// the new page reports through `tab_ready`.
+pub(crate) fn on_page_load(webview: Webview, payload: PageLoadPayload<'_>) {
+    let app = webview.app_handle();
+    if payload.event() == PageLoadEvent::Started
+        && app.state::<TabManager>().mark_loading(webview.label())
+    {
+        emit_tab_state(app);
+    }
+}
+
+/// Called by a tab's page once its route has loaded.
+///
+/// Pool webviews report ready while warming up, before they belong to a tab; those
+/// calls are only recorded for prefetched papers, and the tab is marked when it's claimed.
+#[tauri::command]
+pub fn tab_ready(webview: Webview, app: AppHandle) {
+    let tab_id = webview.label();
+    if !mark_tab_loaded(&app, tab_id) {
+        app.state::<WebviewPool>().mark_prefetch_ready(tab_id);
+    }
+}
+
+/// Called by a tab's page as it scrolls. Doesn't emit tab state, since scrolling
+/// reports often; read it back with `reading_progress`.
+#[tauri::command]
+pub fn report_scroll(webview: Webview, app: AppHandle, scroll_y: f64, page_height: f64) {
+    let scroll = ScrollPosition {
+        scroll_y,
+        page_height,
+    };
+    app.state::<TabManager>()
+        .set_scroll(webview.label(), scroll);
+}
+
+/// Called by a tab's page when it gains or loses unsaved work (e.g. a scratch tab's
+/// buffer), so closing the window asks for confirmation first
+#[tauri::command]
+pub fn set_tab_unsaved(webview: Webview, app: AppHandle, unsaved: bool) {
+    if app
+        .state::<TabManager>()
+        .set_unsaved(webview.label(), unsaved)
+    {
+        emit_tab_state(&app);
+    }
+}
+
+/// Whether closing the window now would discard unsaved scratch work
+#[tauri::command]
+pub fn has_unsaved_scratch(app: AppHandle) -> bool {
+    app.state::<TabManager>().has_unsaved()
+}
+
+/// How far each tab is scrolled through its paper (0-100), in tab order
+#[tauri::command]
+pub fn reading_progress(app: AppHandle) -> Vec<TabProgress> {
+    app.state::<TabManager>().get_state().reading_progress()
+}
+
+/// Acknowledge an in-place navigation (see `route_claimed_webview`), so the webview
+/// isn't reloaded in full
+#[tauri::command]
+pub fn navigate_ack(webview: Webview, app: AppHandle) {
+    app.state::<WebviewPool>().take_ack(webview.label());
+}
+
+/// Start loading a paper in an idle pool webview without showing it, so a following
+/// `create_tab` for the same path claims the already-loaded webview (e.g. on hover).
+///
+/// A no-op if the pool is empty or the paper is already prefetched.
+#[tauri::command]
+pub fn prefetch_paper(app: AppHandle, path: String) -> Result<(), String> {
+    let path_ref = std::path::Path::new(&path);
+    if !path_ref.exists() {
+        return Err(format!("Paper not found: {}", path));
+    }
+    if !path_ref.is_dir() && !is_supported_file(&path) {
+        return Err("unsupported file type".to_string());
+    }
+
+    let tab = tab_for_path(&path);
+    let Some(label) = app.state::<WebviewPool>().prefetch(&tab.tab_type, &path) else {
+        return Ok(());
+    };
+    let webview = app.get_webview(&label).ok_or("Pool webview not found")?;
+    configure_pooled_webview(&webview, &tab.tab_type, Some(&path), None)
+}
+
+/// Navigate webviews to new routes after the papers they show moved.
+///
+/// Pages keep the path they were opened with, so they reload at the new route rather
+/// than going on to read and save the old one.
+pub(crate) fn reroute_webviews(app: &AppHandle, routes: &[(String, String)]) {
+    for (label, route) in routes {
+        if let Some(webview) = app.get_webview(label) {
+            let _ = webview.eval(format!("window.location.replace(\"{}\");", route));
+        }
+    }
+}
+
+/// Turn a tab back into a home tab in place, reusing its webview.
+///
+/// A page can't delete the entries before it, so the library is loaded with
+/// `history=reset`: it pushes itself as the first entry and sends Back from there
+/// forward again, so the earlier pages can't be reached.
+#[tauri::command]
+pub fn reset_tab_to_home(app: AppHandle, id: String) -> Result<(), String> {
+    let manager = app.state::<TabManager>();
+    if manager.get_tab(&id).is_none() {
+        return Err("Tab not found".to_string());
+    }
+
+    if let Some(webview) = app.get_webview(&id) {
+        // Replace (not push) so Back doesn't return to the paper; the reload also resets scroll
+        let script = format!(
+            "window.location.replace(\"{}&history=reset\");",
+            get_tab_route("home", None)
+        );
+        webview.eval(&script).map_err(|e| e.to_string())?;
+    }
+
+    let split = {
+        let mut state = manager.state.lock().unwrap();
+        state.tabs.iter_mut().find(|t| t.id == id).and_then(|tab| {
+            tab.tab_type = "home".to_string();
+            tab.paper_path = None;
+            tab.title = "Library".to_string();
+            tab.loaded = false;
+            tab.scroll = None;
+            tab.split.take()
+        })
+    };
+
+    // The library takes the whole tab, so drop the right half of a split tab
+    if let Some(split) = split {
+        if let Some(webview) = app.get_webview(&split.right_label) {
+            let _ = webview.close();
+        }
+        layout::relayout(&app);
+    }
+    emit_tab_state(&app);
+    Ok(())
+}
+
+/// Open a paper read-only for a shared display, returning the tab id.
+///
+/// The tab gets a fresh webview that can't navigate away from the app, open new
+/// windows or devtools, show a context menu or raise JS dialogs.
+#[tauri::command]
+pub fn create_locked_tab(app: AppHandle, path: String) -> Result<String, String> {
+    if !std::path::Path::new(&path).exists() {
+        return Err(format!("Paper not found: {}", path));
+    }
+    create_tab_internal(&app, locked_tab_request(&path))
+}
+
+/// Locked tabs go through the pool like any other; `TabRequest::plan` gives them a
+/// fresh webview
+fn locked_tab_request(path: &str) -> TabRequest {
+    TabRequest::pooled(TabInfo {
+        locked: true,
+        ..tab_for_path(path)
+    })
+}
+
+/// Open a paper in the peek tab: a temporary tab that closes when another tab is
+/// switched to, until `promote_peek` makes it permanent. Returns the tab id.
+///
+/// There is at most one peek tab; if one is open it's pointed at the new paper.
+#[tauri::command]
+pub fn create_peek_tab(app: AppHandle, path: String) -> Result<String, String> {
+    if !std::path::Path::new(&path).exists() {
+        return Err(format!("Paper not found: {}", path));
+    }
+    let tab = TabInfo {
+        peek: true,
+        ..tab_for_path(&path)
+    };
+    validate_tab(&tab, false)?;
+
     let manager = app.state::<TabManager>();
+    let Some(peek) = manager.get_state().tabs.into_iter().find(|t| t.peek) else {
+        return create_tab_with_pool(&app, tab);
+    };
+
     {
         let mut state = manager.state.lock().unwrap();
-        if let Some(tab) = state.tabs.iter_mut().find(|t| t.id == tab_id) {
-            tab.title = title;
+        if let Some(existing) = state.tabs.iter_mut().find(|t| t.id == peek.id) {
+            existing.tab_type = tab.tab_type.clone();
+            existing.paper_path = tab.paper_path.clone();
+            existing.title = tab.title;
+            existing.loaded = false;
+            existing.scroll = None;
         }
     }
+
+    // A peek tab restored from a session may not have a webview yet; switching
+    // creates it with the new route
+    if let Some(webview) = app.get_webview(&peek.id) {
+        let route = get_tab_route(&tab.tab_type, tab.paper_path.as_deref());
+        let script = format!("window.location.replace(\"{}\");", route);
+        webview.eval(&script).map_err(|e| e.to_string())?;
+    }
+    library::record_recent(&app, &path);
+    switch_tab(app, peek.id.clone())?;
+    Ok(peek.id)
+}
+
+/// Keep a peek tab open when switching away (e.g. on double-clicking it)
+#[tauri::command]
+pub fn promote_peek(app: AppHandle, id: String) -> Result<(), String> {
+    if !app.state::<TabManager>().promote_peek(&id) {
+        return Err("Tab not found".to_string());
+    }
     emit_tab_state(&app);
     Ok(())
 }
 
+/// Open two papers side by side in one tab, returning the tab id.
+///
+/// The tab's own webview shows the left paper; a second webview shows the right one.
+#[tauri::command]
+pub fn create_split_tab(
+    app: AppHandle,
+    left_path: String,
+    right_path: String,
+) -> Result<String, String> {
+    open_split_tab(&app, &left_path, &right_path)
+}
+
+fn open_split_tab(app: &AppHandle, left_path: &str, right_path: &str) -> Result<String, String> {
+    for path in [left_path, right_path] {
+        if !std::path::Path::new(path).exists() {
+            return Err(format!("Paper not found: {}", path));
+        }
+    }
+
+    let left = tab_for_path(left_path);
+    let right = tab_for_path(right_path);
+    let tab = TabInfo {
+        title: format!("{} | {}", left.title, right.title),
+        split: Some(SplitInfo {
+            right_label: format!("tab-{}", Uuid::new_v4()),
+            right_type: right.tab_type,
+            right_path: right_path.to_string(),
+        }),
+        ..left
+    };
+
+    let id = create_tab_with_pool(app, tab)?;
+    ensure_webview(app, &id)?;
+    Ok(id)
+}
+
+/// Delete the temporary file backing an ephemeral tab
+fn remove_ephemeral_file(tab: &TabInfo) {
+    if let Some(path) = &tab.paper_path {
+        if let Err(e) = std::fs::remove_file(path) {
+            log::warn!("Failed to remove ephemeral file {}: {}", path, e);
+        }
+    }
+}
+
+/// Delete the files backing ephemeral tabs that are still open (called on exit)
+pub fn remove_ephemeral_files(app: &AppHandle) {
+    let state = app.state::<TabManager>().get_state();
+    for tab in state.tabs.iter().filter(|t| t.ephemeral) {
+        remove_ephemeral_file(tab);
+    }
+}
+
+/// Describe a tab opening a path: paper directories open as "paper", files as "markdown"
+pub(crate) fn tab_for_path(path: &str) -> TabInfo {
+    let path_ref = std::path::Path::new(path);
+    let tab_type = if path_ref.is_dir() {
+        "paper"
+    } else {
+        "markdown"
+    };
+    let title = path_ref
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string());
+    TabInfo::new(tab_type, Some(path.to_string()), title)
+}
+
+/// Whether any open tab shows the paper (paths are compared in canonical form)
+#[tauri::command]
+pub fn is_paper_open(app: AppHandle, path: String) -> bool {
+    app.state::<TabManager>().is_paper_open(&path)
+}
+
+/// Switch to the tab already showing the paper, returning false if none is open,
+/// so callers can focus an open paper or else open it in one round-trip
+#[tauri::command]
+pub fn switch_to_tab_by_path(app: AppHandle, path: String) -> Result<bool, String> {
+    match app.state::<TabManager>().tab_showing(&path) {
+        Some(id) => switch_tab(app, id).map(|_| true),
+        None => Ok(false),
+    }
+}
+
+/// Whether a closed tab can still be reopened (its paper hasn't been deleted)
+pub(crate) fn can_reopen(tab: &TabInfo) -> bool {
+    match tab.paper_path.as_deref() {
+        Some(path) => std::path::Path::new(path).exists(),
+        None => true,
+    }
+}
+
+/// List recently closed tabs, most recent first, skipping papers that no longer exist
+#[tauri::command]
+pub fn list_recently_closed(app: AppHandle) -> Vec<TabInfo> {
+    let manager = app.state::<TabManager>();
+    manager
+        .get_closed()
+        .into_iter()
+        .filter(can_reopen)
+        .collect()
+}
+
+/// Reopen a specific recently closed tab, returning the new tab id
+#[tauri::command]
+pub fn reopen_closed_tab_by_id(app: AppHandle, id: String) -> Result<String, String> {
+    let manager = app.state::<TabManager>();
+    let tab = manager.take_closed(&id).ok_or("Closed tab not found")?;
+    if !can_reopen(&tab) {
+        return Err("Paper no longer exists".to_string());
+    }
+
+    if let (Some(left_path), Some(split)) = (&tab.paper_path, &tab.split) {
+        return open_split_tab(&app, left_path, &split.right_path);
+    }
+
+    let reopened = TabInfo {
+        theme: tab.theme,
+        color: tab.color,
+        ..TabInfo::new(&tab.tab_type, tab.paper_path, tab.title)
+    };
+    create_tab_with_pool(&app, reopened)
+}
+
 #[tauri::command]
 pub fn close_active_tab(app: AppHandle) -> Result<(), String> {
     let manager = app.state::<TabManager>();
@@ -418,10 +1888,14 @@ pub fn close_active_tab(app: AppHandle) -> Result<(), String> {
 
 /// Helper function for menu event - creates a new home tab using the pool.
 pub fn create_tab_internal_from_menu(app: &AppHandle) -> Result<(), String> {
-    create_tab_with_pool(app, "home", None, "Library".to_string())?;
+    create_tab_internal(app, menu_tab_request())?;
     Ok(())
 }
 
+fn menu_tab_request() -> TabRequest {
+    TabRequest::pooled(home_tab())
+}
+
 /// Helper function for menu event - closes active tab or window if single tab
 pub fn close_tab_or_window(app: &AppHandle) -> Result<(), String> {
     let manager = app.state::<TabManager>();
@@ -442,3 +1916,587 @@ pub fn close_tab_or_window(app: &AppHandle) -> Result<(), String> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn tab(id: &str, tab_type: &str) -> TabInfo {
+        TabInfo {
+            id: id.to_string(),
+            ..TabInfo::new(tab_type, None, "Library".to_string())
+        }
+    }
+
+    #[test]
+    fn set_title_reports_change_once() {
+        let manager = TabManager::new();
+        manager.add_tab(tab("a", "home"));
+
+        assert!(manager.set_title("a", "Attention".to_string()));
+        assert!(!manager.set_title("a", "Attention".to_string()));
+        assert_eq!(manager.get_tab("a").unwrap().title, "Attention");
+    }
+
+    #[test]
+    fn set_title_ignores_unknown_tab() {
+        let manager = TabManager::new();
+        assert!(!manager.set_title("missing", "Attention".to_string()));
+    }
+
+    #[test]
+    fn set_theme_overrides_and_clears() {
+        let manager = TabManager::new();
+        manager.add_tab(tab("a", "paper"));
+
+        assert!(manager.set_theme("a", Some("high-contrast".to_string())));
+        assert_eq!(
+            manager.get_tab("a").unwrap().theme.as_deref(),
+            Some("high-contrast")
+        );
+        assert!(manager.set_theme("a", None));
+        assert_eq!(manager.get_tab("a").unwrap().theme, None);
+        assert!(!manager.set_theme("missing", None));
+    }
+
+    #[test]
+    fn mark_loaded_reports_first_ready_only() {
+        let manager = TabManager::new();
+        manager.add_tab(tab("a", "paper"));
+
+        assert!(manager.mark_loaded("a"));
+        assert!(!manager.mark_loaded("a"));
+        assert!(manager.get_tab("a").unwrap().loaded);
+        assert!(!manager.mark_loaded("pool-warming"));
+
+        // A full navigation starts over until the new page is ready
+        assert!(manager.mark_loading("a"));
+        assert!(!manager.mark_loading("a"));
+        assert!(manager.mark_loaded("a"));
+        assert!(!manager.mark_loading("pool-warming"));
+    }
+
+    #[test]
+    fn hex_colors_must_be_rrggbb() {
+        assert!(is_hex_color("#1a2B3c"));
+        assert!(!is_hex_color("1a2b3c"));
+        assert!(!is_hex_color("#abc"));
+        assert!(!is_hex_color("#gg0000"));
+        assert!(!is_hex_color("#1a2b3c4d"));
+    }
+
+    /// A manager holding paper tabs with the given ids, the first one active
+    fn manager_with(ids: &[&str]) -> TabManager {
+        let manager = TabManager::new();
+        for id in ids {
+            manager.add_tab(tab(id, "paper"));
+        }
+        manager.set_active(ids[0]);
+        manager
+    }
+
+    #[test]
+    fn closing_active_middle_tab_keeps_its_index() {
+        let manager = manager_with(&["a", "b", "c", "d", "e"]);
+        manager.set_active("c");
+
+        assert_eq!(manager.close("c").as_deref(), Some("d"));
+        assert_eq!(manager.get_active_index(), Some(2));
+        assert_eq!(manager.close("d").as_deref(), Some("e"));
+        assert_eq!(manager.get_active_index(), Some(2));
+    }
+
+    #[test]
+    fn closing_active_tabs_clamps_to_the_last_tab() {
+        let manager = manager_with(&["a", "b", "c"]);
+        manager.set_active("c");
+
+        assert_eq!(manager.close("c").as_deref(), Some("b"));
+        assert_eq!(manager.get_active_index(), Some(1));
+        assert_eq!(manager.close("b").as_deref(), Some("a"));
+        assert_eq!(manager.get_active_index(), Some(0));
+
+        // Nothing is left to activate, so the active id doesn't dangle
+        assert_eq!(manager.close("a"), None);
+        assert_eq!(manager.get_active_index(), None);
+        assert_eq!(manager.get_state().active_tab_id, "");
+    }
+
+    #[test]
+    fn closing_tab_before_active_shifts_its_index() {
+        let manager = manager_with(&["a", "b", "c"]);
+        manager.set_active("c");
+
+        assert_eq!(manager.close("a"), None);
+        assert_eq!(manager.get_active_index(), Some(1));
+        assert_eq!(manager.get_tab_at_index(1).unwrap().id, "c");
+    }
+
+    #[test]
+    fn remove_tab_reports_position_without_changing_active() {
+        let manager = manager_with(&["a", "b", "c"]);
+        manager.set_active("b");
+
+        assert_eq!(manager.remove_tab("c"), Some(2));
+        assert_eq!(manager.remove_tab("c"), None);
+        assert_eq!(manager.remove_tab("b"), Some(1));
+
+        // remove_tab is the raw operation; only close picks a new active tab
+        assert_eq!(manager.get_state().active_tab_id, "b");
+        assert_eq!(manager.get_active_index(), None);
+    }
+
+    #[test]
+    fn closing_a_tab_twice_changes_nothing_the_second_time() {
+        let manager = manager_with(&["a", "b", "c"]);
+        manager.set_active("b");
+
+        assert_eq!(manager.close("b").as_deref(), Some("c"));
+        assert_eq!(manager.close("b"), None);
+        let state = manager.get_state();
+        assert_eq!(state.active_tab_id, "c");
+        assert_eq!(state.tabs.len(), 2);
+    }
+
+    #[test]
+    fn closing_background_tab_keeps_active_tab() {
+        let manager = TabManager::new();
+        manager.add_tab(tab("a", "home"));
+        manager.add_tab(tab("b", "paper"));
+        manager.add_tab(tab("c", "paper"));
+        manager.set_active("c");
+
+        assert_eq!(manager.close("a"), None);
+        assert_eq!(manager.get_state().active_tab_id, "c");
+        assert_eq!(manager.tab_count(), 2);
+    }
+
+    #[test]
+    fn closing_active_tab_activates_its_neighbour() {
+        let manager = TabManager::new();
+        manager.add_tab(tab("a", "home"));
+        manager.add_tab(tab("b", "paper"));
+        manager.add_tab(tab("c", "paper"));
+
+        manager.set_active("b");
+        assert_eq!(manager.close("b").as_deref(), Some("c"));
+
+        // Closing the last tab falls back to the new last one
+        assert_eq!(manager.close("c").as_deref(), Some("a"));
+        assert_eq!(manager.get_state().active_tab_id, "a");
+        assert_eq!(manager.close("missing"), None);
+    }
+
+    #[test]
+    fn rename_path_updates_open_and_closed_tabs() {
+        let manager = TabManager::new();
+        manager.add_tab(TabInfo {
+            id: "a".to_string(),
+            ..TabInfo::new("markdown", Some("/p/a.md".to_string()), "a".to_string())
+        });
+        manager.push_closed(TabInfo {
+            id: "b".to_string(),
+            ..TabInfo::new("markdown", Some("/p/a.md".to_string()), "a".to_string())
+        });
+
+        assert_eq!(
+            manager.rename_path("/p/a.md", "/q/b.md"),
+            vec![("a".to_string(), get_tab_route("markdown", Some("/q/b.md")))]
+        );
+        assert!(manager.rename_path("/p/a.md", "/q/c.md").is_empty());
+        let path = |t: TabInfo| t.paper_path.unwrap();
+        assert_eq!(path(manager.get_tab("a").unwrap()), "/q/b.md");
+        assert_eq!(path(manager.take_closed("b").unwrap()), "/q/b.md");
+    }
+
+    #[test]
+    fn tabs_showing_matches_papers_inside_a_folder() {
+        let manager = TabManager::new();
+        for (id, path) in [("a", "/p/x/a.md"), ("b", "/p/xy/b.md")] {
+            manager.add_tab(TabInfo {
+                id: id.to_string(),
+                ..TabInfo::new("markdown", Some(path.to_string()), id.to_string())
+            });
+        }
+        manager.add_tab(tab("home", "home"));
+
+        assert_eq!(manager.tabs_showing("/p/x"), vec!["a"]);
+        assert_eq!(manager.tabs_showing("/p/xy/b.md"), vec!["b"]);
+    }
+
+    #[test]
+    fn tabs_under_matches_whole_folder_names() {
+        let manager = TabManager::new();
+        for (id, path) in [
+            ("a", "/p/x/a.md"),
+            ("b", "/p/x/sub/b.md"),
+            ("c", "/p/xy/c.md"),
+        ] {
+            manager.add_tab(TabInfo {
+                id: id.to_string(),
+                ..TabInfo::new("markdown", Some(path.to_string()), id.to_string())
+            });
+        }
+        manager.add_tab(tab("home", "home"));
+
+        let ids = |dir: &str| -> Vec<String> {
+            manager.tabs_under(dir).into_iter().map(|t| t.id).collect()
+        };
+        assert_eq!(ids("/p/x"), vec!["a", "b"]);
+        assert_eq!(ids("/p/x/"), vec!["a", "b"]);
+        assert_eq!(ids("/p"), vec!["a", "b", "c"]);
+        assert!(ids("/q").is_empty());
+    }
+
+    #[test]
+    fn locked_tabs_only_navigate_within_the_app() {
+        let allowed = |url: &str, dev| is_app_url(&Url::parse(url).unwrap(), dev);
+        assert!(allowed("tauri://localhost/tab?type=home", false));
+        assert!(allowed("http://tauri.localhost/tab", false));
+        assert!(allowed("http://localhost:5173/tab", true));
+        assert!(!allowed("http://localhost:5173/tab", false));
+        assert!(!allowed("https://example.com/", true));
+        assert!(!allowed("file:///etc/passwd", true));
+    }
+
+    #[test]
+    fn only_markdown_files_are_supported() {
+        assert!(is_supported_file("/p/a.md"));
+        assert!(is_supported_file("/p/a.Markdown"));
+        assert!(!is_supported_file("/p/figure.png"));
+        assert!(!is_supported_file("/p/README"));
+    }
+
+    #[test]
+    fn open_papers_include_split_halves() {
+        let manager = TabManager::new();
+        manager.add_tab(TabInfo {
+            id: "a".to_string(),
+            split: Some(SplitInfo {
+                right_label: "tab-b".to_string(),
+                right_type: "markdown".to_string(),
+                right_path: "/p/b.md".to_string(),
+            }),
+            ..TabInfo::new("markdown", Some("/p/a.md".to_string()), "a | b".to_string())
+        });
+        manager.add_tab(tab("home", "home"));
+
+        assert!(manager.is_paper_open("/p/a.md"));
+        assert!(manager.is_paper_open("/p/b.md"));
+        assert!(!manager.is_paper_open("/p/c.md"));
+    }
+
+    #[test]
+    fn close_range_clamps_and_keeps_a_tab_open() {
+        assert_eq!(close_range(1, 3, 5), 1..3);
+        assert_eq!(close_range(2, 99, 5), 2..5);
+        assert_eq!(close_range(7, 9, 5), 5..5);
+        assert_eq!(close_range(3, 1, 5), 1..1);
+        assert_eq!(close_range(0, 99, 3), 0..2);
+    }
+
+    #[test]
+    fn validate_tab_checks_theme_and_file_type() {
+        // What the menu helper and initial tab create
+        assert!(validate_tab(&home_tab(), false).is_ok());
+
+        // What the create_tab command receives
+        let paper = |path: &str| TabInfo::new("markdown", Some(path.to_string()), "a".into());
+        assert!(validate_tab(&paper("/p/a.md"), false).is_ok());
+        assert_eq!(
+            validate_tab(&paper("/p/a.png"), false),
+            Err("unsupported file type".to_string())
+        );
+        assert!(validate_tab(&paper("/p/a.png"), true).is_ok());
+
+        let themed = TabInfo {
+            theme: Some("sepia".to_string()),
+            ..home_tab()
+        };
+        assert!(validate_tab(&themed, true).is_err());
+    }
+
+    #[test]
+    fn entry_points_plan_where_webviews_come_from() {
+        assert_eq!(menu_tab_request().plan(), Ok(WebviewSource::Pool));
+        assert_eq!(initial_tab_request().plan(), Ok(WebviewSource::Fresh));
+
+        let command = |path: &str, force| {
+            create_tab_request("markdown", Some(path.into()), "a".into(), None, force).plan()
+        };
+        assert_eq!(command("/p/a.md", None), Ok(WebviewSource::Pool));
+        assert_eq!(
+            command("/p/a.png", None),
+            Err("unsupported file type".to_string())
+        );
+        assert_eq!(command("/p/a.png", Some(true)), Ok(WebviewSource::Pool));
+
+        let sepia = create_tab_request("home", None, "Library".into(), Some("sepia".into()), None);
+        assert!(sepia.plan().is_err());
+
+        assert_eq!(
+            locked_tab_request("/p/a.md").plan(),
+            Ok(WebviewSource::Fresh)
+        );
+        assert!(locked_tab_request("/p/a.png").plan().is_err());
+    }
+
+    #[test]
+    fn paper_tab_cycling_skips_home_tabs() {
+        let mut state = TabState {
+            tabs: vec![
+                tab("home", "home"),
+                tab("a", "markdown"),
+                tab("b", "paper"),
+                tab("home2", "home"),
+            ],
+            active_tab_id: "b".to_string(),
+        };
+        assert_eq!(state.adjacent_paper_tab(true), Some("a"));
+        assert_eq!(state.adjacent_paper_tab(false), Some("a"));
+
+        state.active_tab_id = "home".to_string();
+        assert_eq!(state.adjacent_paper_tab(true), Some("a"));
+        assert_eq!(state.adjacent_paper_tab(false), Some("b"));
+
+        state.tabs.remove(2);
+        assert_eq!(state.adjacent_paper_tab(true), None);
+    }
+
+    #[test]
+    fn reading_progress_uses_reported_scroll() {
+        let manager = TabManager::new();
+        for (id, tab_type) in [("home", "home"), ("a", "paper"), ("b", "markdown")] {
+            manager.add_tab(tab(id, tab_type));
+        }
+        manager.add_tab(tab("c", "paper"));
+        let scroll = |scroll_y, page_height| ScrollPosition {
+            scroll_y,
+            page_height,
+        };
+        manager.set_scroll("home", scroll(10.0, 100.0));
+        manager.set_scroll("a", scroll(250.0, 1000.0));
+        manager.set_scroll("b", scroll(0.0, 0.0));
+        assert!(!manager.set_scroll("gone", scroll(0.0, 1.0)));
+
+        let percents: Vec<_> = manager
+            .get_state()
+            .reading_progress()
+            .into_iter()
+            .map(|p| p.percent)
+            .collect();
+        assert_eq!(percents, vec![None, Some(25), Some(100), None]);
+
+        assert_eq!(scroll(1200.0, 1000.0).percent(), 100);
+        assert_eq!(scroll(-5.0, 1000.0).percent(), 0);
+    }
+
+    #[test]
+    fn concurrent_creates_leave_one_tab_visible() {
+        let manager = Arc::new(TabManager::new());
+        manager.add_active(tab("home", "home"));
+        let visible = Arc::new(Mutex::new(HashSet::from(["home".to_string()])));
+
+        let threads: Vec<_> = (0..16)
+            .map(|i| {
+                let (manager, visible) = (manager.clone(), visible.clone());
+                std::thread::spawn(move || {
+                    // The new webview shows as it's created, before it's activated
+                    let id = format!("tab-{}", i);
+                    visible.lock().unwrap().insert(id.clone());
+                    activate_new_tab(&manager, tab(&id, "paper"), |previous| {
+                        visible.lock().unwrap().remove(previous);
+                    });
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let state = manager.get_state();
+        assert_eq!(state.tabs.len(), 17);
+        assert_eq!(
+            *visible.lock().unwrap(),
+            HashSet::from([state.active_tab_id])
+        );
+    }
+
+    #[test]
+    fn adjacent_tabs_wrap_around() {
+        let mut state = TabState {
+            tabs: vec![tab("a", "home"), tab("b", "paper"), tab("c", "paper")],
+            active_tab_id: "a".to_string(),
+        };
+        let ids =
+            |adjacent: AdjacentTabs| (adjacent.prev.map(|t| t.id), adjacent.next.map(|t| t.id));
+        assert_eq!(
+            ids(state.adjacent_tabs()),
+            (Some("c".to_string()), Some("b".to_string()))
+        );
+
+        state.active_tab_id = "c".to_string();
+        assert_eq!(
+            ids(state.adjacent_tabs()),
+            (Some("b".to_string()), Some("a".to_string()))
+        );
+
+        state.tabs.truncate(1);
+        assert_eq!(ids(state.adjacent_tabs()), (None, None));
+    }
+
+    /// Read the tab type and path back out of a `get_tab_route` URL the way the page
+    /// does with `URLSearchParams` (`+` is a space, then percent-decoding)
+    fn decode_tab_route(route: &str) -> Option<(String, Option<String>)> {
+        let query = route.strip_prefix("/tab?")?;
+        let mut tab_type = None;
+        let mut path = None;
+        for pair in query.split('&') {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = urlencoding::decode(&value.replace('+', " "))
+                .ok()?
+                .into_owned();
+            match key {
+                "type" => tab_type = Some(value),
+                "path" => path = Some(value),
+                _ => {}
+            }
+        }
+        Some((tab_type?, path))
+    }
+
+    #[test]
+    fn tab_route_round_trips_awkward_paths() {
+        let paths = [
+            "/p/with space/a.md",
+            "/p/a&type=home/b.md",
+            "/p/#notes/c.md",
+            "/p/100%/d+e.md",
+            "/p/ünïcödé/論文.md",
+            "/p/a=b?c/e.md",
+        ];
+        for path in paths {
+            let route = get_tab_route("markdown", Some(path));
+            // Only the query's own `?` and `&` separators survive encoding
+            assert_eq!(route.matches(['?', '&', '#']).count(), 2, "{}", route);
+            assert_eq!(
+                decode_tab_route(&route),
+                Some(("markdown".to_string(), Some(path.to_string())))
+            );
+        }
+        assert_eq!(
+            decode_tab_route(&get_tab_route("home", None)),
+            Some(("home".to_string(), None))
+        );
+    }
+
+    #[test]
+    fn only_other_tabs_close_the_peek_tab() {
+        let manager = manager_with(&["a", "b"]);
+        assert!(manager.peek_to_close("a").is_none());
+
+        let mut peek = tab("peek", "markdown");
+        peek.peek = true;
+        manager.add_tab(peek);
+        assert!(manager.peek_to_close("peek").is_none());
+        assert_eq!(manager.peek_to_close("a").unwrap().id, "peek");
+
+        assert!(manager.promote_peek("peek"));
+        assert!(manager.peek_to_close("a").is_none());
+        assert!(!manager.promote_peek("missing"));
+    }
+
+    #[test]
+    fn closed_peek_tabs_are_not_remembered() {
+        let manager = TabManager::new();
+        let mut peek = tab("peek", "markdown");
+        peek.peek = true;
+        manager.push_closed(peek);
+        assert!(manager.get_closed().is_empty());
+
+        manager.push_closed(tab("b", "markdown"));
+        assert_eq!(manager.get_closed().len(), 1);
+    }
+
+    #[test]
+    fn moving_the_active_tab_stops_at_the_ends() {
+        let manager = manager_with(&["a", "b", "c"]);
+        let ids = |m: &TabManager| {
+            m.get_state()
+                .tabs
+                .into_iter()
+                .map(|t| t.id)
+                .collect::<Vec<_>>()
+        };
+        manager.set_active("a");
+        assert!(!manager.move_active(false));
+        assert!(manager.move_active(true));
+        assert_eq!(ids(&manager), vec!["b", "a", "c"]);
+
+        manager.set_active("c");
+        assert!(!manager.move_active(true));
+        assert!(manager.move_tab("c", 99));
+        assert!(manager.move_tab("c", 0));
+        assert_eq!(ids(&manager), vec!["c", "b", "a"]);
+        assert!(!manager.move_tab("missing", 0));
+    }
+
+    #[test]
+    fn sorting_tabs_keeps_home_tabs_first() {
+        let titled = |id: &str, tab_type: &str, title: &str, last_active_ms| TabInfo {
+            title: title.to_string(),
+            last_active_ms,
+            ..tab(id, tab_type)
+        };
+        let mut tabs = vec![
+            titled("b", "markdown", "beta", 30),
+            titled("h1", "home", "Library", 10),
+            titled("a", "markdown", "Alpha", 20),
+            titled("h2", "home", "Library", 40),
+            titled("c", "paper", "gamma", 0),
+        ];
+        let ids = |tabs: &[TabInfo]| tabs.iter().map(|t| t.id.clone()).collect::<Vec<_>>();
+
+        sort_tab_list(&mut tabs, "title").unwrap();
+        assert_eq!(ids(&tabs), vec!["h1", "h2", "a", "b", "c"]);
+
+        sort_tab_list(&mut tabs, "recent").unwrap();
+        assert_eq!(ids(&tabs), vec!["h1", "h2", "b", "a", "c"]);
+
+        assert!(sort_tab_list(&mut tabs, "size").is_err());
+        assert_eq!(ids(&tabs), vec!["h1", "h2", "b", "a", "c"]);
+    }
+
+    #[test]
+    fn unsaved_tabs_are_tracked_until_cleared() {
+        let manager = TabManager::new();
+        manager.add_tab(tab("a", "paper"));
+        manager.add_tab(tab("b", "home"));
+        assert!(!manager.has_unsaved());
+
+        assert!(manager.set_unsaved("b", true));
+        assert!(!manager.set_unsaved("b", true));
+        assert!(manager.has_unsaved());
+
+        assert!(manager.set_unsaved("b", false));
+        assert!(!manager.has_unsaved());
+        assert!(!manager.set_unsaved("missing", true));
+    }
+
+    #[test]
+    fn tab_showing_prefers_the_active_tab() {
+        let manager = TabManager::new();
+        for (id, path) in [("a", "/p/a.md"), ("b", "/p/b.md"), ("c", "/p/a.md")] {
+            manager.add_tab(TabInfo {
+                id: id.to_string(),
+                ..TabInfo::new("markdown", Some(path.to_string()), id.to_string())
+            });
+        }
+
+        assert_eq!(manager.tab_showing("/p/./a.md").as_deref(), Some("a"));
+        manager.set_active("c");
+        assert_eq!(manager.tab_showing("/p/a.md").as_deref(), Some("c"));
+        assert_eq!(manager.tab_showing("/p/missing.md"), None);
+    }
+}
@@ -0,0 +1,271 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{
+    AppHandle, CloseRequestApi, Emitter, ExitRequestApi, Manager, PhysicalPosition, WebviewUrl,
+    WebviewWindowBuilder,
+};
+use uuid::Uuid;
+
+use crate::settings;
+use crate::tabs::{self, TabManager, TabState};
+
+const WINDOW_POSITION_KEY: &str = "windowPosition";
+
+const ALWAYS_ON_TOP_KEY: &str = "alwaysOnTop";
+
+const TITLE_FOLLOWS_TAB_KEY: &str = "windowTitleFollowsTab";
+
+/// Ids of the View menu and its "Always on Top" checkbox
+pub const VIEW_MENU_ID: &str = "view";
+pub const ALWAYS_ON_TOP_MENU_ID: &str = "always_on_top";
+
+/// Label prefix of the windows opened with `open_new_window`
+pub const EXTRA_WINDOW_PREFIX: &str = "window-";
+
+/// How long the window must stay still before its position is written to the store
+const POSITION_SAVE_DELAY: Duration = Duration::from_millis(300);
+
+/// Last known position of the main window.
+///
+/// `Moved` fires continuously during a drag, so the store is only written once the
+/// window has been still for `POSITION_SAVE_DELAY`; the position here is always current.
+pub struct WindowPosition {
+    latest: Mutex<Option<(i32, i32)>>,
+    last_moved: Mutex<Instant>,
+    /// Whether a trailing save is already waiting for movement to stop
+    save_pending: AtomicBool,
+}
+
+impl WindowPosition {
+    pub fn new() -> Self {
+        Self {
+            latest: Mutex::new(None),
+            last_moved: Mutex::new(Instant::now()),
+            save_pending: AtomicBool::new(false),
+        }
+    }
+
+    pub fn get(&self) -> Option<(i32, i32)> {
+        *self.latest.lock().unwrap()
+    }
+
+    fn record(&self, x: i32, y: i32) {
+        *self.latest.lock().unwrap() = Some((x, y));
+        *self.last_moved.lock().unwrap() = Instant::now();
+    }
+
+    fn idle(&self) -> Duration {
+        self.last_moved.lock().unwrap().elapsed()
+    }
+}
+
+/// Set by `force_quit`, so the exit it triggers isn't held for confirmation again
+pub struct QuitGuard {
+    confirmed: AtomicBool,
+}
+
+impl QuitGuard {
+    pub fn new() -> Self {
+        Self {
+            confirmed: AtomicBool::new(false),
+        }
+    }
+
+    fn confirm(&self) {
+        self.confirmed.store(true, Ordering::Relaxed);
+    }
+
+    fn is_confirmed(&self) -> bool {
+        self.confirmed.load(Ordering::Relaxed)
+    }
+}
+
+/// Move the main window back to where it was last left (called at startup)
+pub fn load_window_position(app: &AppHandle) {
+    let Some((x, y)) = settings::load::<(i32, i32)>(app, WINDOW_POSITION_KEY) else {
+        return;
+    };
+    if let Some(window) = app.get_window("main") {
+        let _ = window.set_position(PhysicalPosition::new(x, y));
+    }
+}
+
+/// Track a move of the main window, persisting the position once movement stops
+pub fn on_moved(app: &AppHandle, position: PhysicalPosition<i32>) {
+    let state = app.state::<WindowPosition>();
+    state.record(position.x, position.y);
+    if state.save_pending.swap(true, Ordering::AcqRel) {
+        return;
+    }
+
+    let app = app.clone();
+    thread::spawn(move || {
+        let state = app.state::<WindowPosition>();
+        loop {
+            let idle = state.idle();
+            if idle >= POSITION_SAVE_DELAY {
+                break;
+            }
+            thread::sleep(POSITION_SAVE_DELAY - idle);
+        }
+
+        // Clear the flag before reading so a move landing now is either saved
+        // here or schedules its own save
+        state.save_pending.store(false, Ordering::Release);
+        if let Some(position) = state.get() {
+            if let Err(e) = settings::save(&app, WINDOW_POSITION_KEY, &position) {
+                log::warn!("Failed to persist window position: {}", e);
+            }
+        }
+    });
+}
+
+/// Whether the main window should stay above other windows (persisted)
+pub fn always_on_top(app: &AppHandle) -> bool {
+    settings::load(app, ALWAYS_ON_TOP_KEY).unwrap_or(false)
+}
+
+/// Pin the main window above others if it was pinned last time (called at startup)
+pub fn load_always_on_top(app: &AppHandle) {
+    if !always_on_top(app) {
+        return;
+    }
+    if let Some(window) = app.get_window("main") {
+        if let Err(e) = window.set_always_on_top(true) {
+            log::warn!("Failed to keep window on top: {}", e);
+        }
+    }
+}
+
+/// Keep the View menu checkbox in step with the window
+fn sync_always_on_top_menu(app: &AppHandle, on: bool) {
+    let Some(view) = app.menu().and_then(|menu| menu.get(VIEW_MENU_ID)) else {
+        return;
+    };
+    let item = view.as_submenu().and_then(|s| s.get(ALWAYS_ON_TOP_MENU_ID));
+    if let Some(check) = item.as_ref().and_then(|i| i.as_check_menuitem()) {
+        let _ = check.set_checked(on);
+    }
+}
+
+/// Keep the main window above other windows, or stop doing so
+#[tauri::command]
+pub fn set_always_on_top(app: AppHandle, on: bool) -> Result<(), String> {
+    let window = app.get_window("main").ok_or("Main window not found")?;
+    window.set_always_on_top(on).map_err(|e| e.to_string())?;
+    sync_always_on_top_menu(&app, on);
+    settings::save(&app, ALWAYS_ON_TOP_KEY, &on)
+}
+
+/// Whether the main window's title shows the active paper (persisted, on by default)
+fn title_follows_tab(app: &AppHandle) -> bool {
+    settings::load(app, TITLE_FOLLOWS_TAB_KEY).unwrap_or(true)
+}
+
+/// The active tab's title, or the app name on a home tab
+fn window_title(state: &TabState, app_name: &str) -> String {
+    state
+        .tabs
+        .iter()
+        .find(|t| t.id == state.active_tab_id && t.tab_type != "home")
+        .map_or_else(|| app_name.to_string(), |t| t.title.clone())
+}
+
+/// Title the main window after the active tab, if enabled (called whenever tab
+/// state is emitted)
+pub fn sync_title(app: &AppHandle, state: &TabState) {
+    if !title_follows_tab(app) {
+        return;
+    }
+    if let Some(window) = app.get_window("main") {
+        let _ = window.set_title(&window_title(state, &app.package_info().name));
+    }
+}
+
+#[tauri::command]
+pub fn get_title_follows_tab(app: AppHandle) -> bool {
+    title_follows_tab(&app)
+}
+
+/// Show the active paper's title as the window title, or always the app name
+#[tauri::command]
+pub fn set_title_follows_tab(app: AppHandle, on: bool) -> Result<(), String> {
+    settings::save(&app, TITLE_FOLLOWS_TAB_KEY, &on)?;
+    let window = app.get_window("main").ok_or("Main window not found")?;
+    let title = if on {
+        let state = app.state::<TabManager>().get_state();
+        window_title(&state, &app.package_info().name)
+    } else {
+        app.package_info().name.clone()
+    };
+    window.set_title(&title).map_err(|e| e.to_string())
+}
+
+/// Hold a request to close the main window while tabs have unsaved scratch work,
+/// emitting `confirm-quit` so the frontend can ask; it calls `force_quit` to go ahead
+pub fn on_close_requested(app: &AppHandle, api: &CloseRequestApi) {
+    if app.state::<QuitGuard>().is_confirmed() || !app.state::<TabManager>().has_unsaved() {
+        return;
+    }
+    api.prevent_close();
+    let _ = app.emit("confirm-quit", ());
+}
+
+/// Hold a request to exit the app (Cmd+Q, the Quit menu item) the same way as
+/// `on_close_requested`, so quitting can't skip the confirmation
+pub fn on_exit_requested(app: &AppHandle, api: &ExitRequestApi) {
+    if app.state::<QuitGuard>().is_confirmed() || !app.state::<TabManager>().has_unsaved() {
+        return;
+    }
+    api.prevent_exit();
+    let _ = app.emit("confirm-quit", ());
+}
+
+/// Quit the app even though tabs have unsaved scratch work
+#[tauri::command]
+pub fn force_quit(app: AppHandle) {
+    app.state::<QuitGuard>().confirm();
+    app.exit(0);
+}
+
+/// Open the library in another window of this app.
+///
+/// Tabs, the pool and the session belong to the "main" window, so the new window is
+/// a single tab-less reading view: it browses and reads papers in place, while
+/// "open in new tab" still opens them in the main window. Running in the same
+/// process means it shares the one settings store instead of racing a copy of it.
+#[tauri::command]
+pub fn open_new_window(app: AppHandle) -> Result<(), String> {
+    let label = format!("{}{}", EXTRA_WINDOW_PREFIX, Uuid::new_v4());
+    let url = WebviewUrl::App(tabs::get_tab_route("home", None).into());
+    WebviewWindowBuilder::new(&app, label, url)
+        .title(app.package_info().name.clone())
+        .inner_size(800.0, 600.0)
+        .build()
+        .map_err(|e| format!("Failed to open new window: {}", e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tabs::TabInfo;
+
+    #[test]
+    fn title_is_the_active_paper_or_app_name() {
+        let tab = |id: &str, tab_type: &str| TabInfo {
+            id: id.to_string(),
+            ..TabInfo::new(tab_type, None, format!("Title {}", id))
+        };
+        let mut state = TabState {
+            tabs: vec![tab("home", "home"), tab("a", "markdown")],
+            active_tab_id: "a".to_string(),
+        };
+        assert_eq!(window_title(&state, "papers"), "Title a");
+
+        state.active_tab_id = "home".to_string();
+        assert_eq!(window_title(&state, "papers"), "papers");
+    }
+}
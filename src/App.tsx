@@ -1,5 +1,7 @@
 import { invoke } from "@tauri-apps/api/core";
-import { useMemo } from "react";
+import { listen } from "@tauri-apps/api/event";
+import { ask } from "@tauri-apps/plugin-dialog";
+import { useEffect, useMemo } from "react";
 import { TabBar } from "@/components/tab-bar";
 import { useCommands } from "@/hooks/use-commands";
 import { useGlobalKeyboardHandler } from "@/hooks/use-keyboard-shortcuts";
@@ -37,6 +39,24 @@ export function App() {
   // Set up global keyboard shortcuts (uses command registry)
   useGlobalKeyboardHandler();
 
+  // The backend holds the window open while scratch tabs have unsaved work
+  useEffect(() => {
+    const unlisten = listen("confirm-quit", async () => {
+      const quit = await ask("Some tabs have unsaved changes. Quit anyway?", {
+        title: "Unsaved Changes",
+        kind: "warning",
+        okLabel: "Quit",
+        cancelLabel: "Cancel",
+      });
+      if (quit) {
+        await invoke("force_quit");
+      }
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   if (isLoading) {
     return (
       <div className="h-screen flex items-center justify-center">
@@ -15,7 +15,7 @@ import type { MarkdownFile, Paper } from "@/lib/papers";
 import { loadMarkdownFile, loadPaper } from "@/lib/papers";
 import { invoke } from "@tauri-apps/api/core";
 import { useCallback, useEffect, useMemo, useState } from "react";
-import { useDarkMode } from "./hooks/use-theme";
+import { useDarkMode, useTabThemeOverride } from "./hooks/use-theme";
 
 /**
  * Inner component that uses the command registry.
@@ -32,8 +32,9 @@ export function TabContent() {
   // Set up cross-webview query synchronization
   useQuerySync();
 
-  // Subscribe to dark mode changes and get toggle function
-  const { toggle: toggleTheme } = useDarkMode();
+  // Subscribe to dark mode changes (honouring this tab's override) and get toggle function
+  const themeOverride = useTabThemeOverride();
+  const { toggle: toggleTheme } = useDarkMode(themeOverride);
 
   // Quick Open (file search) palette state
   const [quickOpenOpen, setQuickOpenOpen] = useState(false);
@@ -150,15 +151,67 @@ export function TabContent() {
     }
   }, [initialTabType, paperPath]);
 
-  // Set up handler for pool webviews to receive tab params dynamically
+  // reset_tab_to_home loads the library with history=reset. Earlier pages stay in
+  // the webview's history, so Back from the library's first entry goes forward again
   useEffect(() => {
-    (
-      window as unknown as {
-        __setTabParams?: (type: string, encodedPath: string | null) => void;
+    const url = new URL(window.location.href);
+    if (url.searchParams.get("history") !== "reset") return;
+    url.searchParams.delete("history");
+    window.history.replaceState({ historyStart: true }, "", url);
+    window.history.pushState(null, "", url);
+
+    const onPopState = (event: PopStateEvent) => {
+      if (event.state?.historyStart) {
+        window.history.forward();
+      }
+    };
+    window.addEventListener("popstate", onPopState);
+    return () => window.removeEventListener("popstate", onPopState);
+  }, []);
+
+  // Tell the backend once this tab's route has finished loading
+  useEffect(() => {
+    if (!isPaperLoading && !isMarkdownLoading) {
+      invoke("tab_ready");
+    }
+  }, [isPaperLoading, isMarkdownLoading]);
+
+  // Report how far the paper is scrolled (for reading progress), at most every 250ms
+  useEffect(() => {
+    let timer: ReturnType<typeof setTimeout> | undefined;
+    const onScroll = (event: Event) => {
+      const target = event.target;
+      // Only the article's scroll container, not e.g. the notes sidebar
+      if (
+        !(target instanceof HTMLElement) ||
+        !target.querySelector(":scope > .paper-scroll-container")
+      ) {
+        return;
       }
-    ).__setTabParams = (type: string, encodedPath: string | null) => {
-      const path = encodedPath ? decodeURIComponent(encodedPath) : null;
+      if (timer) return;
+      timer = setTimeout(() => {
+        timer = undefined;
+        invoke("report_scroll", {
+          scrollY: target.scrollTop,
+          pageHeight: target.scrollHeight - target.clientHeight,
+        }).catch(console.error);
+      }, 250);
+    };
 
+    // Scroll events don't bubble, so listen in the capture phase
+    document.addEventListener("scroll", onScroll, {
+      capture: true,
+      passive: true,
+    });
+    return () => {
+      clearTimeout(timer);
+      document.removeEventListener("scroll", onScroll, { capture: true });
+    };
+  }, []);
+
+  // Set up handlers for pool webviews to receive tab params dynamically
+  useEffect(() => {
+    const applyTabParams = (type: string, path: string | null) => {
       if (type === "paper" && path) {
         setIsPaperLoading(true);
         loadPaper(path)
@@ -209,6 +262,32 @@ export function TabContent() {
       // For "home" type - already showing home, nothing to do
     };
 
+    (
+      window as unknown as {
+        __setTabParams?: (
+          type: string,
+          encodedPath: string | null,
+          route?: string | null,
+        ) => void;
+      }
+    ).__setTabParams = (
+      type: string,
+      encodedPath: string | null,
+      route?: string | null,
+    ) => {
+      // In-place navigation (see route_claimed_webview): acknowledge first so the
+      // backend doesn't fall back to a full reload
+      if (route) {
+        invoke("navigate_ack").catch(console.error);
+        window.history.pushState(null, "", route);
+      }
+      // Both values arrive percent-encoded (see configure_pooled_webview)
+      applyTabParams(
+        decodeURIComponent(type),
+        encodedPath ? decodeURIComponent(encodedPath) : null,
+      );
+    };
+
     return () => {
       delete (window as unknown as { __setTabParams?: unknown }).__setTabParams;
     };
@@ -7,7 +7,11 @@ import {
   CommandItem,
   CommandList,
 } from "@/components/ui/command";
-import { searchFiles, type FileSearchResult } from "@/lib/file-search";
+import {
+  searchFilesDetailed,
+  type FileSearchResult,
+  type SearchResponse,
+} from "@/lib/file-search";
 import { invoke } from "@tauri-apps/api/core";
 import { debounce } from "lodash-es";
 import { useCallback, useEffect, useMemo, useState } from "react";
@@ -16,12 +20,12 @@ import { useCallback, useEffect, useMemo, useState } from "react";
 const debouncedSearchFiles = debounce(
   async (
     query: string,
-    onSuccess: (results: FileSearchResult[]) => void,
+    onSuccess: (response: SearchResponse) => void,
     onError: (err: unknown) => void,
   ) => {
     try {
-      const results = await searchFiles(query);
-      onSuccess(results);
+      const response = await searchFilesDetailed(query);
+      onSuccess(response);
     } catch (err) {
       onError(err);
     }
@@ -40,6 +44,7 @@ export function FileSearchPalette({
 }: FileSearchPaletteProps) {
   const [query, setQuery] = useState("");
   const [results, setResults] = useState<FileSearchResult[]>([]);
+  const [indexEmpty, setIndexEmpty] = useState(false);
   const [selectedId, setSelectedId] = useState<string | null>(null);
 
   // Only search when query is non-empty
@@ -55,8 +60,9 @@ export function FileSearchPalette({
 
     debouncedSearchFiles(
       query,
-      (searchResults) => {
-        setResults(searchResults);
+      (response) => {
+        setResults(response.results);
+        setIndexEmpty(response.index_empty);
       },
       (err) => {
         console.error("File search failed:", err);
@@ -101,11 +107,12 @@ export function FileSearchPalette({
         tabType: "markdown",
         paperPath: result.path,
         title,
+        fromQuery: query,
       });
 
       onOpenChange(false);
     },
-    [onOpenChange],
+    [onOpenChange, query],
   );
 
   const handleKeyDown = useCallback(
@@ -150,7 +157,11 @@ export function FileSearchPalette({
         />
         <CommandList className="max-h-80">
           <CommandEmpty>
-            {query.trim() === "" ? "Type to search..." : "No files found."}
+            {query.trim() === ""
+              ? "Type to search..."
+              : indexEmpty
+                ? "Indexing..."
+                : "No files found."}
           </CommandEmpty>
           <CommandGroup>
             {results.map((result) => (
@@ -1,7 +1,19 @@
 import { Button } from "@/components/ui/button";
 import type { TabInfo } from "@/hooks/use-tab-state";
 import { cn } from "@/lib/utils";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { Plus, X } from "lucide-react";
+import { useEffect, useRef, useState } from "react";
+
+export type TabDensity = "compact" | "comfortable" | "spacious";
+
+/** Bar heights for each density; must match TabDensity::height in layout.rs */
+const TAB_DENSITY_HEIGHTS: Record<TabDensity, number> = {
+  compact: 30,
+  comfortable: 38,
+  spacious: 46,
+};
 
 interface TabBarProps {
   tabs: TabInfo[];
@@ -33,6 +45,12 @@ function TabItem({
       aria-selected={isActive}
       tabIndex={0}
       onClick={onSwitch}
+      onDoubleClick={() => {
+        // Keep a peek tab open
+        if (tab.peek) {
+          invoke("promote_peek", { id: tab.id }).catch(console.error);
+        }
+      }}
       onAuxClick={(e) => {
         // Middle click to close
         if (e.button === 1) {
@@ -74,7 +92,12 @@ function TabItem({
       </button>
 
       {/* Tab title */}
-      <span className="flex-1 truncate text-xs font-medium text-center text-ellipsis">
+      <span
+        className={cn(
+          "flex-1 truncate text-xs font-medium text-center text-ellipsis",
+          tab.peek && "italic",
+        )}
+      >
         {tab.title}
       </span>
 
@@ -96,8 +119,48 @@ export function TabBar({
   onCloseTab,
   onNewTab,
 }: TabBarProps) {
+  const barRef = useRef<HTMLDivElement>(null);
+  // undefined until loaded; null when no density was chosen (CSS default height)
+  const [density, setDensity] = useState<TabDensity | null | undefined>(
+    undefined,
+  );
+
+  // Render the bar at the height the backend lays content webviews out for
+  useEffect(() => {
+    invoke<TabDensity | null>("get_tab_density")
+      .then(setDensity)
+      .catch((err: unknown) => {
+        console.error(err);
+        setDensity(null);
+      });
+    const unlisten = listen<{ density: TabDensity }>(
+      "tab-density-changed",
+      (event) => setDensity(event.payload.density),
+    );
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // Report the rendered height so content webviews sit flush below the bar.
+  // A chosen density overrides --titlebar-height for everything that uses it.
+  useEffect(() => {
+    if (density === undefined) return;
+    if (density) {
+      document.documentElement.style.setProperty(
+        "--titlebar-height",
+        `${TAB_DENSITY_HEIGHTS[density]}px`,
+      );
+    }
+    const height = barRef.current?.getBoundingClientRect().height;
+    if (height) {
+      invoke("set_tab_bar_height", { height }).catch(console.error);
+    }
+  }, [density]);
+
   return (
     <div
+      ref={barRef}
       className="tab-bar fixed top-0 left-0 right-0 flex items-center z-50 bg-muted h-(--titlebar-height) pt-1"
       role="tablist"
     >
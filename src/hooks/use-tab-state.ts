@@ -2,11 +2,43 @@ import { useState, useEffect } from "react";
 import { listen } from "@tauri-apps/api/event";
 import { invoke } from "@tauri-apps/api/core";
 
+export interface SplitInfo {
+  right_label: string;
+  right_type: string;
+  right_path: string;
+}
+
 export interface TabInfo {
   id: string;
   tab_type: string; // "home" | "paper"
   paper_path: string | null;
   title: string;
+  ephemeral: boolean;
+  theme: string | null;
+  color: string | null;
+  loaded: boolean;
+  split: SplitInfo | null;
+  last_active_ms: number;
+  peek: boolean;
+  scroll: ScrollPosition | null;
+  locked: boolean;
+  /** Set via set_tab_unsaved; closing the window asks first */
+  unsaved: boolean;
+}
+
+export interface ScrollPosition {
+  scroll_y: number;
+  page_height: number;
+}
+
+export interface TabProgress {
+  id: string;
+  percent: number | null;
+}
+
+export interface AdjacentTabs {
+  prev: TabInfo | null;
+  next: TabInfo | null;
 }
 
 export interface TabState {
@@ -1,8 +1,11 @@
 import { broadcastInvalidation } from "@/lib/query-invalidation";
 import { queryKeys } from "@/lib/query-keys";
 import { useMutation, useQuery, useQueryClient } from "@tanstack/react-query";
+import type { TabState } from "@/hooks/use-tab-state";
+import { invoke } from "@tauri-apps/api/core";
+import { getCurrentWebview } from "@tauri-apps/api/webview";
 import { load } from "@tauri-apps/plugin-store";
-import { useEffect, useMemo } from "react";
+import { useEffect, useMemo, useState } from "react";
 
 const STORE_KEY = "theme";
 
@@ -20,6 +23,9 @@ function getStore() {
 
 type Theme = "dark" | "light";
 
+/** Per-tab override set via set_tab_theme (null follows the global theme) */
+export type TabTheme = Theme | "high-contrast";
+
 /**
  * Query hook for fetching the current theme.
  */
@@ -60,15 +66,50 @@ export function useToggleThemeMutation() {
   });
 }
 
+/**
+ * Theme override for the current tab's webview.
+ * Seeded from tab state, then kept in sync via tab-theme-changed.
+ */
+export function useTabThemeOverride() {
+  const [override, setOverride] = useState<TabTheme | null>(null);
+
+  useEffect(() => {
+    const webview = getCurrentWebview();
+    let changed = false;
+
+    invoke<TabState>("get_tab_state")
+      .then((state) => {
+        const tab = state.tabs.find((t) => t.id === webview.label);
+        if (tab && !changed) setOverride(tab.theme as TabTheme | null);
+      })
+      .catch(console.error);
+
+    const unlisten = webview.listen<TabTheme | null>(
+      "tab-theme-changed",
+      (event) => {
+        changed = true;
+        setOverride(event.payload);
+      },
+    );
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  return override;
+}
+
 /**
  * Convenience hook that provides theme state and toggle function.
- * Also applies the theme to the DOM.
+ * Also applies the theme (or the tab's override) to the DOM.
  */
-export function useDarkMode() {
+export function useDarkMode(override: TabTheme | null = null) {
   const { data: theme, isLoading } = useThemeQuery();
   const { mutate: toggleTheme } = useToggleThemeMutation();
 
-  const isDark = theme === "dark";
+  const isDark = override ? override !== "light" : theme === "dark";
+  const isHighContrast = override === "high-contrast";
   const isLoaded = !isLoading;
 
   // Apply theme to DOM
@@ -89,6 +130,7 @@ export function useDarkMode() {
     } else {
       root.classList.remove("dark");
     }
+    root.classList.toggle("high-contrast", isHighContrast);
 
     // Remove no-transition class and reset flag
     if (!isLocalMutation) {
@@ -98,14 +140,14 @@ export function useDarkMode() {
       });
     }
     isLocalMutation = false;
-  }, [isDark, isLoading]);
+  }, [isDark, isHighContrast, isLoading]);
 
   const toggle = useMemo(
     () => () => {
-      const newTheme = isDark ? "light" : "dark";
+      const newTheme = theme === "dark" ? "light" : "dark";
       toggleTheme(newTheme);
     },
-    [isDark, toggleTheme],
+    [theme, toggleTheme],
   );
 
   return { isDark, toggle, isLoaded };
@@ -0,0 +1,23 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/** An app action from the backend registry (shared with the native menu) */
+export interface CommandDescriptor {
+  id: string;
+  title: string;
+  /** Tauri accelerator string, e.g. "CmdOrCtrl+T" */
+  accelerator: string | null;
+}
+
+/**
+ * List every action the backend can run by id
+ */
+export async function listAppCommands(): Promise<CommandDescriptor[]> {
+  return invoke<CommandDescriptor[]>("list_commands");
+}
+
+/**
+ * Run a backend action exactly as its menu item would
+ */
+export async function runAppCommand(id: string): Promise<void> {
+  return invoke<void>("run_command", { id });
+}
@@ -4,13 +4,121 @@ export interface FileSearchResult {
   path: string;
   display_path: string;
   score: number;
+  /** Up to two parent folder names, e.g. "ml/transformers" */
+  context: string;
+  /** Favorites are listed first by searchFiles */
+  is_favorite: boolean;
+}
+
+export interface SearchResponse {
+  results: FileSearchResult[];
+  /** The index holds no papers yet (still indexing), as opposed to no matches */
+  index_empty: boolean;
+}
+
+export type SearchScope = "all" | "favorites" | "recents";
+
+/**
+ * Like searchFiles, but also reports whether the index is still empty
+ */
+export async function searchFilesDetailed(
+  query: string,
+  scope: SearchScope = "all",
+  excludeOpen = false,
+  collection?: string,
+): Promise<SearchResponse> {
+  return invoke<SearchResponse>("search_files_detailed", {
+    query,
+    scope,
+    excludeOpen,
+    collection,
+  });
+}
+
+/**
+ * Search for markdown files using fuzzy matching (reads from cached index).
+ * `collection` limits the search to one named collection.
+ */
+export async function searchFiles(
+  query: string,
+  scope: SearchScope = "all",
+  excludeOpen = false,
+  collection?: string,
+): Promise<FileSearchResult[]> {
+  return invoke<FileSearchResult[]>("search_files", {
+    query,
+    scope,
+    excludeOpen,
+    collection,
+  });
+}
+
+/**
+ * Fuzzy search only the given paths (e.g. papers already filtered by tag),
+ * ranked like searchFiles
+ */
+export async function searchIn(
+  query: string,
+  candidates: string[],
+): Promise<FileSearchResult[]> {
+  return invoke<FileSearchResult[]>("search_in", { query, candidates });
+}
+
+/**
+ * Earlier queries that led to opening a paper, for autocomplete (most recent first)
+ */
+export async function searchSuggestions(prefix: string): Promise<string[]> {
+  return invoke<string[]>("search_suggestions", { prefix });
 }
 
 /**
- * Search for markdown files using fuzzy matching (reads from cached index)
+ * Forget the queries behind searchSuggestions
  */
-export async function searchFiles(query: string): Promise<FileSearchResult[]> {
-  return invoke<FileSearchResult[]>("search_files", { query });
+export async function clearSearchHistory(): Promise<void> {
+  return invoke<void>("clear_search_history");
+}
+
+/**
+ * Whether queries are kept for searchSuggestions (on by default)
+ */
+export async function getSearchHistoryEnabled(): Promise<boolean> {
+  return invoke<boolean>("get_search_history_enabled");
+}
+
+/**
+ * Turn the search history on or off; turning it off clears it
+ */
+export async function setSearchHistoryEnabled(enabled: boolean): Promise<void> {
+  return invoke<void>("set_search_history_enabled", { enabled });
+}
+
+export interface Collection {
+  name: string;
+  roots: string[];
+}
+
+/**
+ * List the named collections indexed alongside the search roots
+ */
+export async function listCollections(): Promise<Collection[]> {
+  return invoke<Collection[]>("list_collections");
+}
+
+/**
+ * Set the folders a named collection indexes (an empty list removes it)
+ */
+export async function setCollectionRoots(
+  name: string,
+  roots: string[],
+): Promise<void> {
+  return invoke<void>("set_collection_roots", { name, roots });
+}
+
+/**
+ * Count papers matching a query without fetching them (all papers when empty)
+ */
+export async function countMatches(query: string): Promise<number> {
+  return invoke<number>("count_matches", { query });
 }
 
 /**
@@ -19,3 +127,52 @@ export async function searchFiles(query: string): Promise<FileSearchResult[]> {
 export async function refreshFileIndex(): Promise<void> {
   return invoke<void>("refresh_file_index");
 }
+
+/**
+ * Stop refreshing the index automatically, e.g. during a bulk reorganization
+ */
+export async function pauseFileWatching(): Promise<void> {
+  return invoke<void>("pause_file_watching");
+}
+
+/**
+ * Resume automatic refreshes, refreshing once to catch up
+ */
+export async function resumeFileWatching(): Promise<void> {
+  return invoke<void>("resume_file_watching");
+}
+
+/**
+ * Skip index refreshes on focus during a reading session (unfreezing refreshes once)
+ */
+export async function freezeIndex(frozen: boolean): Promise<void> {
+  return invoke<void>("freeze_index", { frozen });
+}
+
+export async function getIndexFrozen(): Promise<boolean> {
+  return invoke<boolean>("get_index_frozen");
+}
+
+/**
+ * Format raw paths the same way search results display them
+ */
+export async function formatPaths(paths: string[]): Promise<string[]> {
+  return invoke<string[]>("format_paths", { paths });
+}
+
+export interface ContentSearchResult {
+  path: string;
+  display_path: string;
+  /** Lines containing at least one query word */
+  hits: number;
+  snippet: string;
+}
+
+/**
+ * Search paper text for every word of the query, best matches first
+ */
+export async function searchContent(
+  query: string,
+): Promise<ContentSearchResult[]> {
+  return invoke<ContentSearchResult[]>("search_content", { query });
+}
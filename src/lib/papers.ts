@@ -1,6 +1,7 @@
 import type { Author, ExtractedMetadata } from "@/lib/extract/types";
 import { pathExists } from "@/lib/fs";
 import { extractAuthor, extractTitle, parseMarkdown } from "@/lib/markdown";
+import { invoke } from "@tauri-apps/api/core";
 import { readDir, readTextFile, stat } from "@tauri-apps/plugin-fs";
 
 export interface PaperMetadata {
@@ -193,6 +194,19 @@ async function countDirectoryItems(dirPath: string): Promise<number> {
   }
 }
 
+export interface PaperText {
+  content: string;
+  /** The file wasn't valid UTF-8; invalid bytes were replaced */
+  lossy: boolean;
+}
+
+/**
+ * Read a paper's raw markdown (must be inside the search roots)
+ */
+export async function readPaperText(path: string): Promise<PaperText> {
+  return invoke<PaperText>("read_paper", { path });
+}
+
 /**
  * Load a markdown file from its path
  */